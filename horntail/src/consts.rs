@@ -1,2 +1,46 @@
+use crate::crypto::{AES_KEY, IV_SIZE, KEY_SIZE, MapleCipher, MapleTable, MapleTableNone};
+
 pub const MAPLE_VECTOR_EUROPE: [u8; 4] = [0xB9, 0x7D, 0x63, 0xE9];
 pub const MAPLE_VECTOR_GLOBAL: [u8; 4] = [0x4D, 0x23, 0xC7, 0x2B];
+/// regions which don't encrypt wz strings.
+pub const MAPLE_VECTOR_NONE: [u8; 4] = [0x00, 0x00, 0x00, 0x00];
+
+/// IV and AES key preset of a region.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Region {
+    pub name: &'static str,
+    pub iv: [u8; IV_SIZE],
+    pub key: [u8; KEY_SIZE],
+}
+
+impl Region {
+    #[inline]
+    pub const fn new(name: &'static str, iv: [u8; IV_SIZE], key: [u8; KEY_SIZE]) -> Self {
+        Self { name, iv, key }
+    }
+
+    /// `MapleTableNone` for zero IV, the table of IV and key otherwise.
+    pub fn cipher(&self) -> Box<dyn MapleCipher> {
+        if self.iv == MAPLE_VECTOR_NONE {
+            return MapleTableNone.into_boxed();
+        }
+        MapleTable::with_key(self.key, self.iv).into_boxed()
+    }
+}
+
+/// known region presets.
+pub const REGIONS: &[Region] = &[
+    Region::new("gms", MAPLE_VECTOR_GLOBAL, AES_KEY),
+    Region::new("ems", MAPLE_VECTOR_EUROPE, AES_KEY),
+    Region::new("msea", MAPLE_VECTOR_EUROPE, AES_KEY),
+    Region::new("kms", MAPLE_VECTOR_NONE, AES_KEY),
+    Region::new("jms", MAPLE_VECTOR_NONE, AES_KEY),
+    Region::new("cms", MAPLE_VECTOR_NONE, AES_KEY),
+    Region::new("tms", MAPLE_VECTOR_NONE, AES_KEY),
+    Region::new("bms", MAPLE_VECTOR_NONE, AES_KEY),
+];
+
+/// find preset by name, case-insensitive.
+pub fn region(name: &str) -> Option<&'static Region> {
+    REGIONS.iter().find(|r| r.name.eq_ignore_ascii_case(name))
+}
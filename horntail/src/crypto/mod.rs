@@ -5,7 +5,7 @@ mod version;
 
 pub(crate) use snow2::Snow2;
 
-pub use table::AES_KEY;
+pub use table::{AES_KEY, IV_SIZE, KEY_SIZE};
 pub use version::MapleVersion;
 
-pub use table::{MapleCipher, MapleTable, MapleTableNone};
+pub use table::{KeyStream, MapleCipher, MapleTable, MapleTableNone};
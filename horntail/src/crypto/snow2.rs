@@ -87,7 +87,7 @@ impl Snow2 {
 
     #[inline]
     pub fn crypt(&mut self, dst: &mut [u8], is_enc: bool) {
-        if dst.len() % U32_SIZE != 0 {
+        if !dst.len().is_multiple_of(U32_SIZE) {
             panic!("invalid destination length");
         }
 
@@ -1,12 +1,15 @@
 use aes::cipher::{BlockEncrypt, Key, KeyInit};
 use aes::{Aes256, Block};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex, OnceLock};
 
 const BLOCK_SIZE: usize = 16;
-const IV_SIZE: usize = 4;
+pub const IV_SIZE: usize = 4;
+pub const KEY_SIZE: usize = 32;
 
-pub const AES_KEY: [u8; 32] = [
+pub const AES_KEY: [u8; KEY_SIZE] = [
     0x13, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0xB4, 0x00, 0x00, 0x00,
     0x1B, 0x00, 0x00, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x33, 0x00, 0x00, 0x00, 0x52, 0x00, 0x00, 0x00,
 ];
@@ -37,54 +40,152 @@ impl MapleCipher for MapleTableNone {
     }
 }
 
+const SEGMENT_BASE_SIZE: usize = 0x1000;
+const SEGMENT_COUNT: usize = 32;
+
+/// append-only AES keystream of a key and IV, keystream of zero IV is all
+/// zeros.
+///
+/// table is split into segments double the size of previous one, segments
+/// are generated in order under a lock and never change once set, so reads
+/// of generated part don't lock.
+pub struct KeyStream {
+    aes: Aes256,
+    zero: bool,
+    iv_block: [u8; BLOCK_SIZE],
+    segments: [OnceLock<Box<[u8]>>; SEGMENT_COUNT],
+    grow: Mutex<()>,
+}
+
+impl KeyStream {
+    pub fn new(key: [u8; KEY_SIZE], iv: [u8; IV_SIZE]) -> Self {
+        let mut iv_block = [0; BLOCK_SIZE];
+        iv_block
+            .chunks_exact_mut(IV_SIZE)
+            .for_each(|chunk| chunk.copy_from_slice(&iv));
+        KeyStream {
+            aes: Aes256::new(Key::<Aes256>::from_slice(&key)),
+            zero: iv == [0; IV_SIZE],
+            iv_block,
+            segments: std::array::from_fn(|_| OnceLock::new()),
+            grow: Mutex::new(()),
+        }
+    }
+
+    /// process-wide keystream of `key` and `iv`.
+    pub fn shared(key: [u8; KEY_SIZE], iv: [u8; IV_SIZE]) -> Arc<KeyStream> {
+        type Streams = HashMap<([u8; KEY_SIZE], [u8; IV_SIZE]), Arc<KeyStream>>;
+        static STREAMS: OnceLock<Mutex<Streams>> = OnceLock::new();
+        let mut streams = STREAMS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        streams
+            .entry((key, iv))
+            .or_insert_with(|| Arc::new(KeyStream::new(key, iv)))
+            .clone()
+    }
+
+    #[inline]
+    fn segment_size(index: usize) -> usize {
+        SEGMENT_BASE_SIZE << index
+    }
+
+    /// precompute at least `size` bytes.
+    pub fn reserve(&self, size: usize) {
+        let mut end = 0;
+        for index in 0..SEGMENT_COUNT {
+            if end >= size {
+                return;
+            }
+            end += self.segment(index).len();
+        }
+    }
+
+    fn segment(&self, index: usize) -> &[u8] {
+        if let Some(segment) = self.segments[index].get() {
+            return segment;
+        }
+
+        let _guard = self.grow.lock().unwrap_or_else(|e| e.into_inner());
+        for i in 0..=index {
+            if self.segments[i].get().is_some() {
+                continue;
+            }
+            let mut block = Block::clone_from_slice(match i {
+                0 => &self.iv_block,
+                _ => {
+                    let prev = self.segments[i - 1].get().expect("previous segment");
+                    &prev[prev.len() - BLOCK_SIZE..]
+                }
+            });
+            let mut segment = vec![0; Self::segment_size(i)].into_boxed_slice();
+            if self.zero {
+                let _ = self.segments[i].set(segment);
+                continue;
+            }
+            segment.chunks_exact_mut(BLOCK_SIZE).for_each(|chunk| {
+                self.aes.encrypt_block(&mut block);
+                chunk.copy_from_slice(&block);
+            });
+            let _ = self.segments[i].set(segment);
+        }
+        self.segments[index].get().expect("generated segment")
+    }
+
+    /// xor `dst` with keystream start from the beginning.
+    pub fn apply(&self, dst: &mut [u8]) {
+        let mut dst = dst;
+        let mut index = 0;
+        while !dst.is_empty() {
+            let segment = self.segment(index);
+            let n = segment.len().min(dst.len());
+            let (head, tail) = dst.split_at_mut(n);
+            head.iter_mut().zip(segment).for_each(|(d, t)| *d ^= *t);
+            dst = tail;
+            index += 1;
+        }
+    }
+}
+
+/// cloning shares the keystream, so it's cheap.
 #[derive(Clone)]
 pub struct MapleTable {
-    aes: Aes256,
-    tab: Vec<u8>,
+    stream: Arc<KeyStream>,
 }
 
 impl MapleTable {
+    /// table of the default `AES_KEY`.
+    #[inline]
     pub fn new(iv: [u8; IV_SIZE]) -> Self {
-        let mut tab = std::iter::repeat_n(iv, BLOCK_SIZE / IV_SIZE)
-            .flatten()
-            .collect::<Vec<_>>();
-        let aes = aes::Aes256::new(Key::<Aes256>::from_slice(&AES_KEY));
-        // encrypt first block
-        aes.encrypt_block(Block::from_mut_slice(&mut tab));
-        Self { aes, tab }
+        Self::with_key(AES_KEY, iv)
     }
 
     #[inline]
-    fn extend_table(&mut self, size: usize) {
-        let cipher = &self.aes;
-        let reserve_size = size.saturating_sub(self.tab.len());
-        if reserve_size == 0 {
-            return;
+    pub fn with_key(key: [u8; KEY_SIZE], iv: [u8; IV_SIZE]) -> Self {
+        Self {
+            stream: KeyStream::shared(key, iv),
         }
-        let aligned_size = (reserve_size + BLOCK_SIZE - 1) & !(BLOCK_SIZE - 1);
-        self.tab.reserve(aligned_size);
-        let mut block = [0u8; BLOCK_SIZE];
-        (self.tab.len()..self.tab.capacity())
-            .step_by(BLOCK_SIZE)
-            .for_each(|i| {
-                block.copy_from_slice(&self.tab[i - BLOCK_SIZE..i]);
-                cipher.encrypt_block(Block::from_mut_slice(&mut block));
-                self.tab.extend_from_slice(&block);
-            });
     }
 
-    fn alloc(&mut self, size: usize) -> &[u8] {
-        if self.tab.len() < size {
-            self.extend_table(size);
-        }
-        &self.tab.as_slice()[..size]
+    /// same as `new` but precompute `size` bytes of keystream.
+    #[inline]
+    pub fn with_capacity(iv: [u8; IV_SIZE], size: usize) -> Self {
+        let table = Self::new(iv);
+        table.stream.reserve(size);
+        table
+    }
+
+    #[inline]
+    pub fn stream(&self) -> &Arc<KeyStream> {
+        &self.stream
     }
 }
 
 impl MapleCipher for MapleTable {
+    #[inline]
     fn crypt(&mut self, dst: &mut [u8]) {
-        let table = self.alloc(dst.len());
-        dst.iter_mut().zip(table).for_each(|(dst, t)| *dst ^= *t);
+        self.stream.apply(dst);
     }
 
     #[inline(always)]
@@ -107,3 +208,82 @@ where
         Box::new(self.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IV: [u8; IV_SIZE] = [0x4d, 0x23, 0xc7, 0x2b];
+
+    /// keystream generated block by block, each block encrypts the previous.
+    fn sequential(key: [u8; KEY_SIZE], iv: [u8; IV_SIZE], size: usize) -> Vec<u8> {
+        let aes = Aes256::new(Key::<Aes256>::from_slice(&key));
+        let mut block = Block::clone_from_slice(&iv.repeat(BLOCK_SIZE / IV_SIZE));
+        let mut stream = Vec::with_capacity(size);
+        while stream.len() < size {
+            aes.encrypt_block(&mut block);
+            stream.extend_from_slice(&block);
+        }
+        stream.truncate(size);
+        stream
+    }
+
+    fn keystream(stream: &KeyStream, size: usize) -> Vec<u8> {
+        let mut dst = vec![0; size];
+        stream.apply(&mut dst);
+        dst
+    }
+
+    #[test]
+    fn segments_match_sequential() {
+        // across the first three segment boundaries
+        let size = (SEGMENT_BASE_SIZE << 3) + 17;
+        let expected = sequential(AES_KEY, IV, size);
+
+        let stream = KeyStream::new(AES_KEY, IV);
+        // short reads grow the stream on the way
+        for size in [5, SEGMENT_BASE_SIZE - 1, SEGMENT_BASE_SIZE + 1] {
+            assert_eq!(keystream(&stream, size), expected[..size]);
+        }
+        assert_eq!(keystream(&stream, size), expected);
+
+        let stream = KeyStream::new(AES_KEY, IV);
+        stream.reserve(size);
+        assert_eq!(keystream(&stream, size), expected);
+    }
+
+    #[test]
+    fn custom_key() {
+        let key = [7; KEY_SIZE];
+        let size = SEGMENT_BASE_SIZE * 3;
+        let stream = KeyStream::new(key, IV);
+        assert_eq!(keystream(&stream, size), sequential(key, IV, size));
+    }
+
+    #[test]
+    fn zero_iv_is_zeros() {
+        let stream = KeyStream::new(AES_KEY, [0; IV_SIZE]);
+        let size = SEGMENT_BASE_SIZE * 3;
+        assert!(keystream(&stream, size).iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn shared_by_key_and_iv() {
+        let stream = KeyStream::shared(AES_KEY, IV);
+        assert!(Arc::ptr_eq(&stream, &KeyStream::shared(AES_KEY, IV)));
+        assert!(Arc::ptr_eq(&stream, MapleTable::new(IV).stream()));
+        assert!(!Arc::ptr_eq(&stream, &KeyStream::shared([7; KEY_SIZE], IV)));
+        assert!(!Arc::ptr_eq(
+            &stream,
+            &KeyStream::shared(AES_KEY, [1; IV_SIZE])
+        ));
+
+        // crypting twice with clones gives back the data
+        let mut table = MapleTable::new(IV);
+        let mut data = b"green snail".to_vec();
+        table.crypt(&mut data);
+        assert_ne!(data, b"green snail");
+        table.clone().crypt(&mut data);
+        assert_eq!(data, b"green snail");
+    }
+}
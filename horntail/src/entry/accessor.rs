@@ -1,4 +1,4 @@
-use crate::reader::Accessor;
+use crate::reader::{Accessor, BinaryAccessor};
 use std::io::SeekFrom;
 
 pub trait AccessorBuilder {
@@ -12,6 +12,11 @@ pub trait AccessorBuilder {
     fn clone_boxed(&self) -> Box<dyn AccessorBuilder>;
 
     fn accessor(&self) -> Box<dyn Accessor>;
+
+    /// accessor borrowing the data in memory, `None` when the data isn't.
+    fn binary(&self) -> Option<BinaryAccessor<&[u8]>> {
+        None
+    }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -107,3 +112,13 @@ pub trait TryFromAccessor: Sized {
         accessor: &mut dyn Accessor,
     ) -> Result<Self, Self::Error>;
 }
+
+/// parse from a mapped slice, the result may borrow the payload from it.
+pub trait TryFromBinary<'a>: Sized {
+    type Error;
+
+    fn try_from_binary(
+        opt: AccessorOpt,
+        accessor: &mut BinaryAccessor<&'a [u8]>,
+    ) -> Result<Self, Self::Error>;
+}
@@ -1,9 +1,11 @@
 use crate::Vector2D;
+use crate::entry::canvas::dxt;
 use crate::error::Error;
-use crate::reader::Accessor;
-use crate::{AccessorOpt, Properties, TryFromAccessor};
+use crate::reader::{Accessor, BinaryAccessor};
+use crate::{AccessorOpt, Properties, TryFromAccessor, TryFromBinary};
 #[cfg(feature = "image")]
 use image::*;
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 use std::io::{Read, SeekFrom};
 use std::ops::Deref;
@@ -96,6 +98,13 @@ pub struct CanvasAttribute {
     pub property: Option<Properties>,
     pub size: Vector2D,
     pub format: CanvasFormat,
+    /// magLevel, each stored pixel covers `1 << scale` pixels square, it's
+    /// also added to the format value.
+    pub scale: u8,
+    /// 4 bytes follow the format, render flags of newer clients.
+    pub flags: u32,
+    /// byte leads the data.
+    pub header: u8,
     pub data_size: usize,
 }
 
@@ -108,23 +117,27 @@ impl TryFromAccessor for CanvasAttribute {
     ) -> Result<Self, Self::Error> {
         let property = Properties::builtin(opt, accessor)?;
         let size = Vector2D::try_from_accessor(opt, accessor)?;
-        let format = CanvasFormat::from(accessor.get_var_i32_le() + accessor.get_u8() as i32);
+        let format = accessor.get_var_i32_le();
+        let scale = accessor.get_u8();
+        let format = CanvasFormat::from(format + scale as i32);
 
         if let CanvasFormat::Unknown(format) = format {
             return Err(Error::UnexpectedData(format!("canvas format {format}")));
         }
 
-        // unknown bytes
-        accessor.advance(4);
+        let flags = accessor.get_u32_le();
 
-        // has zero bytes at start useless
+        // size includes the header byte
         let data_size = (accessor.get_i32_le() - 1) as usize;
-        accessor.advance(1);
+        let header = accessor.get_u8();
 
         Ok(CanvasAttribute {
             property,
             size,
             format,
+            scale,
+            flags,
+            header,
             data_size,
         })
     }
@@ -148,6 +161,25 @@ impl Canvas {
     }
 }
 
+#[cfg(test)]
+impl Canvas {
+    /// canvas of `format` over raw `data` without the property.
+    pub(crate) fn with_data(size: Vector2D, format: CanvasFormat, data: Vec<u8>) -> Canvas {
+        Canvas {
+            attr: CanvasAttribute {
+                property: None,
+                size,
+                format,
+                scale: 0,
+                flags: 0,
+                header: 0,
+                data_size: data.len(),
+            },
+            data,
+        }
+    }
+}
+
 impl TryFromAccessor for Canvas {
     type Error = Error;
 
@@ -157,57 +189,121 @@ impl TryFromAccessor for Canvas {
     ) -> Result<Self, Self::Error> {
         let attr = CanvasAttribute::try_from_accessor(opt, accessor)?;
 
-        let flag = accessor.get_u16_le();
-        accessor.try_seek(SeekFrom::Current(-2))?;
-
-        let data = if flag != ZLIB_HEADER_BYTE {
-            // decrypt image data
-            let mut de_data = Vec::with_capacity(attr.data_size);
-            let mut progress = 0;
-            while accessor.has_remaining() && progress < attr.data_size {
-                let size = accessor.get_i32_le() as usize;
-                let off = de_data.len();
-                // make dirty data
-                unsafe {
-                    de_data.set_len(off + size);
-                }
-                accessor.decrypt_to_slice(&mut de_data[off..off + size]);
-                progress += size + 4;
-            }
-            de_data
+        let data = if is_encrypted(accessor)? {
+            decrypt_data(&attr, accessor)
         } else {
             accessor.copy_to_vec(attr.data_size)
         };
 
-        let raw_data_size = attr.format.data_size(attr.size.x, attr.size.y) as usize;
-        let mut zlib_dec = flate2::read::ZlibDecoder::new_with_buf(
-            &*data,
-            vec![0; raw_data_size.max(attr.data_size).min(32 * 1024)],
-        );
+        let raw_data = inflate_data(&attr, &data)?;
+        Ok(Canvas {
+            attr,
+            data: raw_data,
+        })
+    }
+}
 
-        let mut raw_data = vec![0; raw_data_size];
+/// canvas with compressed data, which borrowed from the mapped slice unless
+/// it's encrypted.
+#[derive(Clone)]
+pub struct CanvasRef<'a> {
+    pub attr: CanvasAttribute,
+    data: Cow<'a, [u8]>,
+}
 
-        let mut de_size = 0;
-        while de_size < raw_data_size {
-            let deflate_size = zlib_dec.read(&mut raw_data[de_size..])?;
-            if deflate_size == 0 {
-                break;
-            }
-            de_size += deflate_size;
-        }
+impl<'a> CanvasRef<'a> {
+    /// zlib compressed data.
+    #[inline]
+    pub fn compressed(&self) -> &[u8] {
+        &self.data
+    }
 
-        if de_size != raw_data_size {
-            return Err(Error::BrokenFile);
-        }
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.data, Cow::Borrowed(_))
+    }
+
+    #[inline]
+    pub fn inflate(&self) -> Result<Vec<u8>, Error> {
+        inflate_data(&self.attr, &self.data)
+    }
 
+    #[inline]
+    pub fn into_canvas(self) -> Result<Canvas, Error> {
+        let data = self.inflate()?;
         Ok(Canvas {
-            attr,
-            data: raw_data,
+            attr: self.attr,
+            data,
         })
     }
 }
 
-#[cfg(feature = "image")]
+impl<'a> TryFromBinary<'a> for CanvasRef<'a> {
+    type Error = Error;
+
+    fn try_from_binary(
+        opt: AccessorOpt,
+        accessor: &mut BinaryAccessor<&'a [u8]>,
+    ) -> Result<Self, Self::Error> {
+        let attr = CanvasAttribute::try_from_accessor(opt, accessor)?;
+
+        let data = if is_encrypted(accessor)? {
+            Cow::Owned(decrypt_data(&attr, accessor))
+        } else {
+            Cow::Borrowed(accessor.try_borrow(attr.data_size)?)
+        };
+
+        Ok(CanvasRef { attr, data })
+    }
+}
+
+#[inline]
+fn is_encrypted(accessor: &mut dyn Accessor) -> Result<bool, Error> {
+    let flag = accessor.get_u16_le();
+    accessor.try_seek(SeekFrom::Current(-2))?;
+    Ok(flag != ZLIB_HEADER_BYTE)
+}
+
+fn decrypt_data(attr: &CanvasAttribute, accessor: &mut dyn Accessor) -> Vec<u8> {
+    let mut de_data = Vec::with_capacity(attr.data_size);
+    let mut progress = 0;
+    while accessor.has_remaining() && progress < attr.data_size {
+        let size = accessor.get_i32_le() as usize;
+        let off = de_data.len();
+        // make dirty data
+        unsafe {
+            de_data.set_len(off + size);
+        }
+        accessor.decrypt_to_slice(&mut de_data[off..off + size]);
+        progress += size + 4;
+    }
+    de_data
+}
+
+fn inflate_data(attr: &CanvasAttribute, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let raw_data_size = attr.format.data_size(attr.size.x, attr.size.y) as usize;
+    let mut zlib_dec = flate2::read::ZlibDecoder::new_with_buf(
+        data,
+        vec![0; raw_data_size.max(attr.data_size).min(32 * 1024)],
+    );
+
+    let mut raw_data = vec![0; raw_data_size];
+
+    let mut de_size = 0;
+    while de_size < raw_data_size {
+        let deflate_size = zlib_dec.read(&mut raw_data[de_size..])?;
+        if deflate_size == 0 {
+            break;
+        }
+        de_size += deflate_size;
+    }
+
+    if de_size != raw_data_size {
+        return Err(Error::BrokenFile);
+    }
+    Ok(raw_data)
+}
+
 macro_rules! to_bit8 {
     (5,$val:expr) => {{
         let val = $val as u8;
@@ -224,138 +320,225 @@ macro_rules! to_bit8 {
     (1,$val:expr) => {{ if $val & 1 == 1 { u8::MAX } else { u8::MIN } }};
 }
 
-#[cfg(feature = "image")]
+/// channel order of decoded pixels, 8 bits per channel.
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+pub enum PixelLayout {
+    #[default]
+    Rgba8,
+    Bgra8,
+}
+
+impl PixelLayout {
+    pub const BYTES_PER_PIXEL: usize = 4;
+
+    #[inline]
+    fn write(&self, dst: &mut [u8], rgba: [u8; 4]) {
+        match self {
+            PixelLayout::Rgba8 => dst.copy_from_slice(&rgba),
+            PixelLayout::Bgra8 => dst.copy_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]),
+        }
+    }
+}
+
 impl Canvas {
-    const DXT3_5_DECODED_BYTES_PER_BLOCK: usize = 64;
-    const DXT3_5_ENCODED_BYTES_PER_BLOCK: usize = 16;
+    const DXT_ENCODED_BYTES_PER_BLOCK: usize = 16;
+    const DXT_DECODED_BYTES_PER_BLOCK: usize = 64;
+    const THUMB_BLOCK_SIZE: usize = 16;
 
-    pub fn image(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    #[inline]
+    fn width(&self) -> usize {
+        self.attr.size.x.max(0) as usize
+    }
+
+    #[inline]
+    fn height(&self) -> usize {
+        self.attr.size.y.max(0) as usize
+    }
+
+    /// bytes written by `decode_into`.
+    #[inline]
+    pub fn decoded_size(&self) -> usize {
+        self.width() * self.height() * PixelLayout::BYTES_PER_PIXEL
+    }
+
+    /// raw blocks of block compressed formats for uploading to gpu directly,
+    /// `Gray` is stored in DXT3 blocks.
+    pub fn compressed_blocks(&self) -> Option<&[u8]> {
         match self.attr.format {
-            CanvasFormat::RGB565 => self.rgb565_to_rgba(),
-            CanvasFormat::RGB565Thumb => self.rgb565_thumb_to_rgba(),
-            CanvasFormat::BGRA4444 => self.bgra4444_to_rgba(),
-            CanvasFormat::BGRA8888 => self.bgra8888_to_rgba(),
-            CanvasFormat::ARGB1555 => self.argb1555_to_rgba(),
-            CanvasFormat::Gray | CanvasFormat::DXT3 => self.dxt3_to_rgba(),
-            CanvasFormat::DXT5 => self.dxt5_to_rgba(),
-            CanvasFormat::RGBA1010102 => self.rgba1010102_to_rgba(),
-            CanvasFormat::BC7 => {
-                // TODO: not implement
-                None
-            }
+            CanvasFormat::DXT1
+            | CanvasFormat::DXT3
+            | CanvasFormat::DXT5
+            | CanvasFormat::Gray
+            | CanvasFormat::BC7 => Some(&self.data),
             _ => None,
         }
     }
 
+    /// decode pixels into the front `decoded_size` bytes of `dst` without
+    /// intermediate allocation.
+    pub fn decode_into(&self, dst: &mut [u8], layout: PixelLayout) -> Result<(), Error> {
+        let size = self.decoded_size();
+        let Some(dst) = dst.get_mut(..size) else {
+            return Err(Error::InvalidArgument);
+        };
+        match self.attr.format {
+            CanvasFormat::RGB565 => self.decode_pixels(dst, layout, 2, rgb565_to_rgba)?,
+            CanvasFormat::RGB565Thumb => self.decode_thumb(dst, layout),
+            CanvasFormat::BGRA4444 => self.decode_pixels(dst, layout, 2, bgra4444_to_rgba)?,
+            CanvasFormat::BGRA8888 => {
+                self.decode_pixels(dst, layout, 4, |c| [c[2], c[1], c[0], c[3]])?
+            }
+            CanvasFormat::ARGB1555 => self.decode_pixels(dst, layout, 2, argb1555_to_rgba)?,
+            CanvasFormat::Gray | CanvasFormat::DXT3 => {
+                self.decode_blocks(dst, layout, dxt::decode_dxt3_block)?
+            }
+            CanvasFormat::DXT5 => self.decode_blocks(dst, layout, dxt::decode_dxt5_block)?,
+            CanvasFormat::RGBA1010102 => self.decode_pixels(dst, layout, 4, rgba1010102_to_rgba)?,
+            format => {
+                // TODO: BC7 not implement
+                return Err(Error::UnexpectedData(format!(
+                    "decode canvas format {format}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// truncated data is an error rather than pixels left untouched.
+    #[inline]
+    fn check_size(&self, size: usize) -> Result<(), Error> {
+        if self.data.len() < size {
+            return Err(Error::UnexpectedData(format!(
+                "canvas {} data of {} bytes, expect {size}",
+                self.attr.format,
+                self.data.len()
+            )));
+        }
+        Ok(())
+    }
+
     #[inline]
-    fn process_chunk<F, U>(&self, size: usize, cb: F) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>>
+    fn decode_pixels<F>(
+        &self,
+        dst: &mut [u8],
+        layout: PixelLayout,
+        size: usize,
+        f: F,
+    ) -> Result<(), Error>
     where
-        F: Fn(&[u8]) -> U,
-        U: IntoIterator<Item = u8>,
+        F: Fn(&[u8]) -> [u8; 4],
     {
-        let data = self
-            .data
+        self.check_size(self.width() * self.height() * size)?;
+        self.data
             .chunks_exact(size)
-            .flat_map(cb)
-            .collect::<Vec<_>>();
-        ImageBuffer::from_raw(self.attr.size.x as u32, self.attr.size.y as u32, data)
-    }
-
-    fn bgra4444_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.process_chunk(2, |chunk| {
-            let bits = u16::from_le_bytes([chunk[0], chunk[1]]);
-            [
-                to_bit8!(4, (bits >> 8) & 0xF),
-                to_bit8!(4, (bits >> 4) & 0xF),
-                to_bit8!(4, (bits & 0xF)),
-                to_bit8!(4, (bits >> 12) & 0xF),
-            ]
-        })
-    }
-
-    fn bgra8888_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.process_chunk(4, |chunk| [chunk[2], chunk[1], chunk[0], chunk[3]])
+            .zip(dst.chunks_exact_mut(PixelLayout::BYTES_PER_PIXEL))
+            .for_each(|(chunk, pixel)| layout.write(pixel, f(chunk)));
+        Ok(())
     }
 
-    fn dxt3_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        use crate::entry::canvas::dxt;
-
-        let width_block = (self.attr.size.x / 4) as usize;
-        let per_line_size = Self::DXT3_5_DECODED_BYTES_PER_BLOCK * width_block;
+    fn decode_blocks(
+        &self,
+        dst: &mut [u8],
+        layout: PixelLayout,
+        decode: fn(&[u8], &mut [u8]),
+    ) -> Result<(), Error> {
+        let (width, height) = (self.width(), self.height());
+        let blocks_per_row = width.div_ceil(4);
+        if blocks_per_row == 0 {
+            return Ok(());
+        }
+        let blocks = blocks_per_row * height.div_ceil(4);
+        self.check_size(blocks * Self::DXT_ENCODED_BYTES_PER_BLOCK)?;
 
-        self.process_chunk(
-            Self::DXT3_5_ENCODED_BYTES_PER_BLOCK * width_block,
-            |chunk| {
-                let mut block = vec![0u8; per_line_size];
-                dxt::decode_dxt3_row(chunk, &mut block);
-                block
-            },
-        )
-    }
-
-    fn argb1555_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.process_chunk(2, |chunk| {
-            let color = u16::from_le_bytes([chunk[0], chunk[1]]);
-            [
-                to_bit8!(5, (color >> 10) & 0x1F),
-                to_bit8!(5, (color >> 5) & 0x1F),
-                to_bit8!(5, (color & 0x1F)),
-                to_bit8!(1, color >> 0xF),
-            ]
-        })
+        let mut block = [0u8; Self::DXT_DECODED_BYTES_PER_BLOCK];
+        for (i, chunk) in self
+            .data
+            .chunks_exact(Self::DXT_ENCODED_BYTES_PER_BLOCK)
+            .enumerate()
+        {
+            decode(chunk, &mut block);
+            let (bx, by) = ((i % blocks_per_row) * 4, (i / blocks_per_row) * 4);
+            for (line, y) in (by..height.min(by + 4)).enumerate() {
+                for (col, x) in (bx..width.min(bx + 4)).enumerate() {
+                    let src = (line * 4 + col) * PixelLayout::BYTES_PER_PIXEL;
+                    let off = (y * width + x) * PixelLayout::BYTES_PER_PIXEL;
+                    layout.write(
+                        &mut dst[off..off + PixelLayout::BYTES_PER_PIXEL],
+                        [block[src], block[src + 1], block[src + 2], block[src + 3]],
+                    );
+                }
+            }
+        }
+        Ok(())
     }
 
-    #[inline]
-    fn rgb565_to_rgba_color(chunk: &[u8]) -> [u8; 4] {
-        let color = u16::from_le_bytes([chunk[0], chunk[1]]);
-        [
-            to_bit8!(5, (color >> 11) & 0x1F),
-            to_bit8!(6, (color >> 5) & 0x3F),
-            to_bit8!(5, color & 0x1F),
-            u8::MAX,
-        ]
-    }
-
-    fn rgb565_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.process_chunk(2, Self::rgb565_to_rgba_color)
-    }
-
-    fn rgb565_thumb_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.process_chunk(((self.attr.size.x / 16) * 2) as usize, |block| {
-            block
-                .chunks_exact(2)
-                .flat_map(|chunk| Self::rgb565_to_rgba_color(chunk).repeat(16))
-                .collect::<Vec<_>>()
-                .repeat(16)
-        })
+    /// each color covers a 16x16 block.
+    fn decode_thumb(&self, dst: &mut [u8], layout: PixelLayout) {
+        let width = self.width();
+        let blocks_per_row = width / Self::THUMB_BLOCK_SIZE;
+        dst.chunks_exact_mut(PixelLayout::BYTES_PER_PIXEL)
+            .enumerate()
+            .for_each(|(i, pixel)| {
+                let (x, y) = (i % width, i / width);
+                let index =
+                    (y / Self::THUMB_BLOCK_SIZE) * blocks_per_row + x / Self::THUMB_BLOCK_SIZE;
+                if let Some(chunk) = self.data.get(index * 2..index * 2 + 2) {
+                    layout.write(pixel, rgb565_to_rgba(chunk));
+                }
+            });
     }
+}
 
-    fn dxt5_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        use crate::entry::canvas::dxt;
+#[inline]
+fn bgra4444_to_rgba(chunk: &[u8]) -> [u8; 4] {
+    let bits = u16::from_le_bytes([chunk[0], chunk[1]]);
+    [
+        to_bit8!(4, (bits >> 8) & 0xF),
+        to_bit8!(4, (bits >> 4) & 0xF),
+        to_bit8!(4, (bits & 0xF)),
+        to_bit8!(4, (bits >> 12) & 0xF),
+    ]
+}
 
-        let width_block = (self.attr.size.x / 4) as usize;
-        let per_line_size = Self::DXT3_5_DECODED_BYTES_PER_BLOCK * width_block;
+#[inline]
+fn argb1555_to_rgba(chunk: &[u8]) -> [u8; 4] {
+    let color = u16::from_le_bytes([chunk[0], chunk[1]]);
+    [
+        to_bit8!(5, (color >> 10) & 0x1F),
+        to_bit8!(5, (color >> 5) & 0x1F),
+        to_bit8!(5, (color & 0x1F)),
+        to_bit8!(1, color >> 0xF),
+    ]
+}
 
-        self.process_chunk(
-            Self::DXT3_5_ENCODED_BYTES_PER_BLOCK * width_block,
-            |chunk| {
-                let mut block = vec![0u8; per_line_size];
-                dxt::decode_dxt5_row(chunk, &mut block);
-                block
-            },
-        )
-    }
-
-    fn rgba1010102_to_rgba(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.process_chunk(4, |chunk| {
-            let color = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-            [
-                ((color >> 24) & 0xff) as u8,
-                ((color >> 14) & 0xff) as u8,
-                ((color >> 4) & 0xff) as u8,
-                ((color & 0x03) * 85) as u8,
-            ]
-        })
+#[inline]
+fn rgb565_to_rgba(chunk: &[u8]) -> [u8; 4] {
+    let color = u16::from_le_bytes([chunk[0], chunk[1]]);
+    [
+        to_bit8!(5, (color >> 11) & 0x1F),
+        to_bit8!(6, (color >> 5) & 0x3F),
+        to_bit8!(5, color & 0x1F),
+        u8::MAX,
+    ]
+}
+
+#[inline]
+fn rgba1010102_to_rgba(chunk: &[u8]) -> [u8; 4] {
+    let color = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    [
+        ((color >> 24) & 0xff) as u8,
+        ((color >> 14) & 0xff) as u8,
+        ((color >> 4) & 0xff) as u8,
+        ((color & 0x03) * 85) as u8,
+    ]
+}
+
+#[cfg(feature = "image")]
+impl Canvas {
+    pub fn image(&self) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let mut data = vec![0; self.decoded_size()];
+        self.decode_into(&mut data, PixelLayout::Rgba8).ok()?;
+        ImageBuffer::from_raw(self.width() as u32, self.height() as u32, data)
     }
 }
 
@@ -542,3 +725,103 @@ impl TryFromAccessor for Video {
         Ok(Video { attribute, frames })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// DXT3 block of one opaque RGB565 `color`.
+    fn dxt3_block(color: u16) -> [u8; 16] {
+        let mut block = [0xff; 16];
+        block[8..10].copy_from_slice(&color.to_le_bytes());
+        block[10..].fill(0);
+        block
+    }
+
+    fn decode(canvas: &Canvas, layout: PixelLayout) -> Result<Vec<u8>, Error> {
+        let mut dst = vec![0; canvas.decoded_size()];
+        canvas.decode_into(&mut dst, layout).map(|_| dst)
+    }
+
+    #[test]
+    fn decode_pixels_in_layouts() {
+        let size = Vector2D { x: 2, y: 1 };
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let canvas = Canvas::with_data(size, CanvasFormat::BGRA8888, data);
+        assert_eq!(
+            decode(&canvas, PixelLayout::Rgba8).unwrap(),
+            [3, 2, 1, 4, 7, 6, 5, 8]
+        );
+        assert_eq!(
+            decode(&canvas, PixelLayout::Bgra8).unwrap(),
+            [1, 2, 3, 4, 5, 6, 7, 8]
+        );
+
+        let canvas = Canvas::with_data(size, CanvasFormat::RGB565, vec![0x00, 0xf8, 0x1f, 0x00]);
+        assert_eq!(
+            decode(&canvas, PixelLayout::Rgba8).unwrap(),
+            [0xff, 0, 0, 0xff, 0, 0, 0xff, 0xff]
+        );
+        assert_eq!(
+            decode(&canvas, PixelLayout::Bgra8).unwrap(),
+            [0, 0, 0xff, 0xff, 0xff, 0, 0, 0xff]
+        );
+
+        // destination shorter than the canvas
+        let mut dst = [0; 4];
+        assert!(matches!(
+            canvas.decode_into(&mut dst, PixelLayout::Rgba8),
+            Err(Error::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn decode_blocks_clipped() {
+        // 5x5 takes 2x2 blocks, the right and bottom blocks are clipped
+        let data = [0xf800, 0x07e0, 0x001f, 0xffff]
+            .into_iter()
+            .flat_map(dxt3_block)
+            .collect::<Vec<_>>();
+        let canvas = Canvas::with_data(Vector2D { x: 5, y: 5 }, CanvasFormat::DXT3, data);
+        let pixels = decode(&canvas, PixelLayout::Rgba8).unwrap();
+        assert_eq!(pixels.len(), 5 * 5 * 4);
+        let pixel = |x: usize, y: usize| &pixels[(y * 5 + x) * 4..(y * 5 + x) * 4 + 4];
+        assert_eq!(pixel(3, 3), [0xff, 0, 0, 0xff]);
+        assert_eq!(pixel(4, 0), [0, 0xff, 0, 0xff]);
+        assert_eq!(pixel(0, 4), [0, 0, 0xff, 0xff]);
+        assert_eq!(pixel(4, 4), [0xff, 0xff, 0xff, 0xff]);
+
+        let pixels = decode(&canvas, PixelLayout::Bgra8).unwrap();
+        assert_eq!(&pixels[..4], [0, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn truncated_data() {
+        let size = Vector2D { x: 2, y: 2 };
+        let canvas = Canvas::with_data(size, CanvasFormat::BGRA8888, vec![0; 15]);
+        assert!(matches!(
+            decode(&canvas, PixelLayout::Rgba8),
+            Err(Error::UnexpectedData(_))
+        ));
+
+        // 5x5 needs 4 blocks
+        let data = dxt3_block(0).repeat(3);
+        let canvas = Canvas::with_data(Vector2D { x: 5, y: 5 }, CanvasFormat::DXT3, data);
+        assert!(matches!(
+            decode(&canvas, PixelLayout::Rgba8),
+            Err(Error::UnexpectedData(_))
+        ));
+    }
+
+    #[test]
+    fn compressed_blocks_of_block_formats() {
+        let size = Vector2D { x: 4, y: 4 };
+        let block = dxt3_block(0xf800).to_vec();
+        for format in [CanvasFormat::DXT3, CanvasFormat::Gray, CanvasFormat::BC7] {
+            let canvas = Canvas::with_data(size, format, block.clone());
+            assert_eq!(canvas.compressed_blocks(), Some(block.as_slice()));
+        }
+        let canvas = Canvas::with_data(size, CanvasFormat::BGRA8888, vec![0; 64]);
+        assert_eq!(canvas.compressed_blocks(), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! lossless containers of block compressed canvases
+//!
+//! # Related Links
+//! * <https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dds-header>
+//! * <https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html>
+//! * <https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html>
+
+use crate::{Canvas, CanvasFormat};
+
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+const DDS_HEADER_SIZE: u32 = 124;
+const DDS_PIXEL_FORMAT_SIZE: u32 = 32;
+// caps | height | width | pixel format | linear size
+const DDS_FLAGS: u32 = 0x1 | 0x2 | 0x4 | 0x1000 | 0x80000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DXGI_FORMAT_BC7_UNORM: u32 = 98;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+// identifier, 9 header fields, index and one level
+const KTX2_LEVEL_INDEX_END: usize = 12 + 9 * 4 + 4 * 4 + 2 * 8 + 3 * 8;
+const KHR_DF_VERSION: u32 = 2;
+const KHR_DF_PRIMARIES_BT709: u32 = 1;
+const KHR_DF_TRANSFER_LINEAR: u32 = 1;
+const KHR_DF_CHANNEL_COLOR: u8 = 0;
+const KHR_DF_CHANNEL_BC1A_ALPHA_PRESENT: u8 = 1;
+const KHR_DF_CHANNEL_ALPHA: u8 = 15;
+
+/// block compression of canvas data.
+struct BlockCompression {
+    fourcc: &'static [u8; 4],
+    block_size: usize,
+    vk_format: u32,
+    df_model: u32,
+    /// channel of each sample, samples split a block evenly.
+    samples: &'static [u8],
+}
+
+const BC1: BlockCompression = BlockCompression {
+    fourcc: b"DXT1",
+    block_size: 8,
+    vk_format: 133,
+    df_model: 128,
+    samples: &[KHR_DF_CHANNEL_BC1A_ALPHA_PRESENT],
+};
+
+const BC2: BlockCompression = BlockCompression {
+    fourcc: b"DXT3",
+    block_size: 16,
+    vk_format: 135,
+    df_model: 129,
+    samples: &[KHR_DF_CHANNEL_ALPHA, KHR_DF_CHANNEL_COLOR],
+};
+
+const BC3: BlockCompression = BlockCompression {
+    fourcc: b"DXT5",
+    block_size: 16,
+    vk_format: 137,
+    df_model: 130,
+    samples: &[KHR_DF_CHANNEL_ALPHA, KHR_DF_CHANNEL_COLOR],
+};
+
+const BC7: BlockCompression = BlockCompression {
+    fourcc: b"DX10",
+    block_size: 16,
+    vk_format: 145,
+    df_model: 134,
+    samples: &[KHR_DF_CHANNEL_COLOR],
+};
+
+impl BlockCompression {
+    fn from_format(format: CanvasFormat) -> Option<&'static BlockCompression> {
+        match format {
+            CanvasFormat::DXT1 => Some(&BC1),
+            CanvasFormat::DXT3 | CanvasFormat::Gray => Some(&BC2),
+            CanvasFormat::DXT5 => Some(&BC3),
+            CanvasFormat::BC7 => Some(&BC7),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn sample_bits(&self) -> usize {
+        self.block_size * 8 / self.samples.len()
+    }
+}
+
+#[inline]
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+#[inline]
+fn put_u64(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+impl Canvas {
+    /// raw blocks in a dds file, `None` when canvas is not block compressed.
+    pub fn to_dds(&self) -> Option<Vec<u8>> {
+        let bc = BlockCompression::from_format(self.attr.format)?;
+        let data = self.data();
+        let mut buf = Vec::with_capacity(4 + DDS_HEADER_SIZE as usize + 20 + data.len());
+        buf.extend_from_slice(DDS_MAGIC);
+        put_u32(&mut buf, DDS_HEADER_SIZE);
+        put_u32(&mut buf, DDS_FLAGS);
+        put_u32(&mut buf, self.attr.size.y as u32);
+        put_u32(&mut buf, self.attr.size.x as u32);
+        put_u32(&mut buf, data.len() as u32);
+        // depth, mipmap count and reserved
+        (0..13).for_each(|_| put_u32(&mut buf, 0));
+        put_u32(&mut buf, DDS_PIXEL_FORMAT_SIZE);
+        put_u32(&mut buf, DDPF_FOURCC);
+        buf.extend_from_slice(bc.fourcc);
+        // bit count and masks
+        (0..5).for_each(|_| put_u32(&mut buf, 0));
+        put_u32(&mut buf, DDSCAPS_TEXTURE);
+        // caps2, caps3, caps4 and reserved
+        (0..4).for_each(|_| put_u32(&mut buf, 0));
+        if bc.fourcc == b"DX10" {
+            put_u32(&mut buf, DXGI_FORMAT_BC7_UNORM);
+            put_u32(&mut buf, D3D10_RESOURCE_DIMENSION_TEXTURE2D);
+            // misc flag, array size, misc flags2
+            put_u32(&mut buf, 0);
+            put_u32(&mut buf, 1);
+            put_u32(&mut buf, 0);
+        }
+        buf.extend_from_slice(data);
+        Some(buf)
+    }
+
+    /// raw blocks in a ktx2 file, `None` when canvas is not block compressed.
+    pub fn to_ktx2(&self) -> Option<Vec<u8>> {
+        let bc = BlockCompression::from_format(self.attr.format)?;
+        let data = self.data();
+
+        let descriptor_size = 24 + 16 * bc.samples.len();
+        let dfd_size = 4 + descriptor_size;
+        let dfd_end = KTX2_LEVEL_INDEX_END + dfd_size;
+        // level data aligned to lcm(block size, 4)
+        let data_offset = dfd_end.next_multiple_of(bc.block_size);
+
+        let mut buf = Vec::with_capacity(data_offset + data.len());
+        buf.extend_from_slice(&KTX2_IDENTIFIER);
+        put_u32(&mut buf, bc.vk_format);
+        // type size
+        put_u32(&mut buf, 1);
+        put_u32(&mut buf, self.attr.size.x as u32);
+        put_u32(&mut buf, self.attr.size.y as u32);
+        // depth, layer count
+        put_u32(&mut buf, 0);
+        put_u32(&mut buf, 0);
+        // face count, level count
+        put_u32(&mut buf, 1);
+        put_u32(&mut buf, 1);
+        // supercompression scheme
+        put_u32(&mut buf, 0);
+
+        put_u32(&mut buf, KTX2_LEVEL_INDEX_END as u32);
+        put_u32(&mut buf, dfd_size as u32);
+        // no key/value and supercompression global data
+        put_u32(&mut buf, 0);
+        put_u32(&mut buf, 0);
+        put_u64(&mut buf, 0);
+        put_u64(&mut buf, 0);
+
+        put_u64(&mut buf, data_offset as u64);
+        put_u64(&mut buf, data.len() as u64);
+        put_u64(&mut buf, data.len() as u64);
+
+        put_u32(&mut buf, dfd_size as u32);
+        // vendor khronos, basic descriptor type
+        put_u32(&mut buf, 0);
+        put_u32(&mut buf, KHR_DF_VERSION | (descriptor_size as u32) << 16);
+        put_u32(
+            &mut buf,
+            bc.df_model | KHR_DF_PRIMARIES_BT709 << 8 | KHR_DF_TRANSFER_LINEAR << 16,
+        );
+        // 4x4 texel block, dimensions minus one
+        put_u32(&mut buf, 3 | 3 << 8);
+        put_u32(&mut buf, bc.block_size as u32);
+        put_u32(&mut buf, 0);
+        let sample_bits = bc.sample_bits();
+        bc.samples.iter().enumerate().for_each(|(i, channel)| {
+            let offset = (i * sample_bits) as u32;
+            let length = (sample_bits - 1) as u32;
+            put_u32(&mut buf, offset | length << 16 | (*channel as u32) << 24);
+            // sample position
+            put_u32(&mut buf, 0);
+            // sample lower and upper
+            put_u32(&mut buf, 0);
+            put_u32(&mut buf, u32::MAX);
+        });
+
+        buf.resize(data_offset, 0);
+        buf.extend_from_slice(data);
+        Some(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vector2D;
+
+    fn u32_at(buf: &[u8], pos: usize) -> u32 {
+        u32::from_le_bytes(buf[pos..pos + 4].try_into().unwrap())
+    }
+
+    fn u64_at(buf: &[u8], pos: usize) -> u64 {
+        u64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap())
+    }
+
+    /// 8x4 canvas, two blocks of `block_size`.
+    fn canvas(format: CanvasFormat, block_size: usize) -> Canvas {
+        let data = (0..2 * block_size).map(|i| i as u8).collect();
+        Canvas::with_data(Vector2D { x: 8, y: 4 }, format, data)
+    }
+
+    #[test]
+    fn dds_header() {
+        for (format, fourcc, block_size) in [
+            (CanvasFormat::DXT1, b"DXT1", 8),
+            (CanvasFormat::DXT3, b"DXT3", 16),
+            (CanvasFormat::DXT5, b"DXT5", 16),
+            (CanvasFormat::BC7, b"DX10", 16),
+        ] {
+            let canvas = canvas(format, block_size);
+            let dds = canvas.to_dds().unwrap();
+            assert_eq!(&dds[..4], DDS_MAGIC);
+            assert_eq!(u32_at(&dds, 4), DDS_HEADER_SIZE);
+            assert_eq!(u32_at(&dds, 8), DDS_FLAGS);
+            assert_eq!((u32_at(&dds, 12), u32_at(&dds, 16)), (4, 8));
+            assert_eq!(u32_at(&dds, 20), 2 * block_size as u32);
+            assert_eq!(u32_at(&dds, 76), DDS_PIXEL_FORMAT_SIZE);
+            assert_eq!(u32_at(&dds, 80), DDPF_FOURCC);
+            assert_eq!(&dds[84..88], fourcc);
+            assert_eq!(u32_at(&dds, 108), DDSCAPS_TEXTURE);
+
+            let mut data_offset = 4 + DDS_HEADER_SIZE as usize;
+            if fourcc == b"DX10" {
+                assert_eq!(u32_at(&dds, data_offset), DXGI_FORMAT_BC7_UNORM);
+                assert_eq!(
+                    u32_at(&dds, data_offset + 4),
+                    D3D10_RESOURCE_DIMENSION_TEXTURE2D
+                );
+                assert_eq!(u32_at(&dds, data_offset + 12), 1);
+                data_offset += 20;
+            }
+            assert_eq!(&dds[data_offset..], canvas.data());
+        }
+    }
+
+    #[test]
+    fn ktx2_header() {
+        for (format, vk_format, df_model, samples, block_size) in [
+            (CanvasFormat::DXT1, 133, 128, 1, 8),
+            (CanvasFormat::DXT3, 135, 129, 2, 16),
+            (CanvasFormat::DXT5, 137, 130, 2, 16),
+            (CanvasFormat::BC7, 145, 134, 1, 16),
+        ] {
+            let canvas = canvas(format, block_size);
+            let ktx2 = canvas.to_ktx2().unwrap();
+            assert_eq!(ktx2[..12], KTX2_IDENTIFIER);
+            assert_eq!(u32_at(&ktx2, 12), vk_format);
+            assert_eq!((u32_at(&ktx2, 20), u32_at(&ktx2, 24)), (8, 4));
+            // face and level count
+            assert_eq!((u32_at(&ktx2, 36), u32_at(&ktx2, 40)), (1, 1));
+
+            let dfd_offset = u32_at(&ktx2, 48) as usize;
+            let dfd_size = u32_at(&ktx2, 52) as usize;
+            assert_eq!(dfd_offset, KTX2_LEVEL_INDEX_END);
+            assert_eq!(dfd_size, 4 + 24 + 16 * samples);
+            assert_eq!(u32_at(&ktx2, dfd_offset), dfd_size as u32);
+            assert_eq!(u32_at(&ktx2, dfd_offset + 12) & 0xff, df_model);
+            assert_eq!(u32_at(&ktx2, dfd_offset + 16), 3 | 3 << 8);
+            assert_eq!(u32_at(&ktx2, dfd_offset + 20), block_size as u32);
+
+            let level_offset = u64_at(&ktx2, 80) as usize;
+            assert!(level_offset >= dfd_offset + dfd_size);
+            assert_eq!(level_offset % block_size, 0);
+            assert_eq!(u64_at(&ktx2, 88), canvas.data().len() as u64);
+            assert_eq!(u64_at(&ktx2, 96), canvas.data().len() as u64);
+            assert_eq!(&ktx2[level_offset..], canvas.data());
+        }
+    }
+
+    #[test]
+    fn uncompressed_has_no_container() {
+        let canvas = canvas(CanvasFormat::BGRA8888, 16);
+        assert!(canvas.to_dds().is_none());
+        assert!(canvas.to_ktx2().is_none());
+    }
+}
@@ -53,6 +53,7 @@ fn alpha_table_dxt5(alpha0: u8, alpha1: u8) -> [u8; 8] {
 
 /// decodes an 8-byte dxt color block into the RGB channels of a 16xRGB or 16xRGBA block.
 /// source should have a length of 8, dest a length of 48 (RGB) or 64 (RGBA)
+#[allow(clippy::needless_range_loop)]
 fn decode_dxt_colors(source: &[u8], dest: &mut [u8], is_dxt1: bool) {
     // sanity checks, also enable the compiler to elide all following bound checks
     assert!(source.len() == 8 && (dest.len() == 48 || dest.len() == 64));
@@ -96,7 +97,7 @@ fn decode_dxt_colors(source: &[u8], dest: &mut [u8], is_dxt1: bool) {
 }
 
 /// Decodes a 16-byte bock of dxt5 data to a 16xRGBA block
-fn decode_dxt5_block(source: &[u8], dest: &mut [u8]) {
+pub(crate) fn decode_dxt5_block(source: &[u8], dest: &mut [u8]) {
     assert!(source.len() == 16 && dest.len() == 64);
 
     // extract alpha index table (stored as little endian 64-bit value)
@@ -118,7 +119,7 @@ fn decode_dxt5_block(source: &[u8], dest: &mut [u8]) {
 }
 
 /// Decodes a 16-byte bock of dxt3 data to a 16xRGBA block
-fn decode_dxt3_block(source: &[u8], dest: &mut [u8]) {
+pub(crate) fn decode_dxt3_block(source: &[u8], dest: &mut [u8]) {
     assert!(source.len() == 16 && dest.len() == 64);
 
     // extract alpha index table (stored as little endian 64-bit value)
@@ -135,45 +136,3 @@ fn decode_dxt3_block(source: &[u8], dest: &mut [u8]) {
     // handle colors
     decode_dxt_colors(&source[8..16], dest, false);
 }
-
-/// Decode a row of DXT3 data to four rows of RGBA data.
-/// `source.len()` should be a multiple of 16, otherwise this panics.
-pub(crate) fn decode_dxt3_row(source: &[u8], dest: &mut [u8]) {
-    assert!(source.len() % 16 == 0);
-    let block_count = source.len() / 16;
-    assert!(dest.len() >= block_count * 64);
-
-    // contains the 16 decoded pixels per block
-    let mut decoded_block = [0u8; 64];
-
-    for (x, encoded_block) in source.chunks(16).enumerate() {
-        decode_dxt3_block(encoded_block, &mut decoded_block);
-
-        // copy the values from the decoded block to linewise RGB layout
-        for line in 0..4 {
-            let offset = (block_count * line + x) * 16;
-            dest[offset..offset + 16].copy_from_slice(&decoded_block[line * 16..(line + 1) * 16]);
-        }
-    }
-}
-
-/// Decode a row of DXT5 data to four rows of RGBA data.
-/// `source.len()` should be a multiple of 16, otherwise this panics.
-pub(crate) fn decode_dxt5_row(source: &[u8], dest: &mut [u8]) {
-    assert!(source.len() % 16 == 0);
-    let block_count = source.len() / 16;
-    assert!(dest.len() >= block_count * 64);
-
-    // contains the 16 decoded pixels per block
-    let mut decoded_block = [0u8; 64];
-
-    for (x, encoded_block) in source.chunks(16).enumerate() {
-        decode_dxt5_block(encoded_block, &mut decoded_block);
-
-        // copy the values from the decoded block to linewise RGB layout
-        for line in 0..4 {
-            let offset = (block_count * line + x) * 16;
-            dest[offset..offset + 16].copy_from_slice(&decoded_block[line * 16..(line + 1) * 16]);
-        }
-    }
-}
@@ -1,6 +1,6 @@
 #[allow(clippy::module_inception)]
 mod canvas;
+mod container;
 pub use canvas::*;
 
-#[cfg(feature = "image")]
 mod dxt;
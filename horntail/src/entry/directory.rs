@@ -4,6 +4,7 @@ use std::io::SeekFrom;
 use std::ops::Deref;
 
 const UNKNOWN: u8 = 1;
+const UNKNOWN_SIZE: usize = 10;
 const UOL: u8 = 2;
 const FOLDER: u8 = 3;
 const IMAGE: u8 = 4;
@@ -14,6 +15,55 @@ pub struct Directory {
     pub kind: EntryKind,
     pub offset: usize,
     pub parent_offset: usize,
+    pub size: usize,
+    /// all the data byte sum together
+    pub checksum: i32,
+    /// record bytes of `EntryKind::Unknown`, named `unknown@<offset>` as the
+    /// record has no name.
+    pub raw: Option<[u8; UNKNOWN_SIZE]>,
+}
+
+impl Directory {
+    /// start position of the image data, folder has no data.
+    #[inline]
+    pub fn data_offset(&self) -> Option<usize> {
+        match self.kind {
+            EntryKind::Unknown | EntryKind::Folder => None,
+            _ => Some(self.parent_offset),
+        }
+    }
+
+    /// recompute checksum of the image data, folder is always `0`.
+    pub fn calc_checksum(&self, accessor: &mut dyn Accessor) -> Result<i32, Error> {
+        let Some(offset) = self.data_offset() else {
+            return Ok(0);
+        };
+        if offset + self.size > accessor.len() {
+            return Err(Error::BrokenFile);
+        }
+        seek_back(accessor, SeekFrom::Start(offset as u64), |accessor| {
+            let mut buffer = [0; 0x1000];
+            let mut remaining = self.size;
+            let mut checksum = 0i32;
+            while remaining > 0 {
+                let n = remaining.min(buffer.len());
+                accessor.copy_to_slice(&mut buffer[..n]);
+                checksum = buffer[..n]
+                    .iter()
+                    .fold(checksum, |acc, b| acc.wrapping_add(*b as i32));
+                remaining -= n;
+            }
+            Ok(checksum)
+        })
+    }
+
+    #[inline]
+    pub fn verify(&self, accessor: &mut dyn Accessor) -> Result<bool, Error> {
+        if self.data_offset().is_none() {
+            return Ok(true);
+        }
+        Ok(self.calc_checksum(accessor)? == self.checksum)
+    }
 }
 
 impl TryFromAccessor for Option<Directory> {
@@ -25,8 +75,18 @@ impl TryFromAccessor for Option<Directory> {
     ) -> Result<Self, Self::Error> {
         let kind = accessor.get_u8();
         if kind == UNKNOWN {
-            accessor.advance(10);
-            return Ok(None);
+            let offset = accessor.pos();
+            let mut raw = [0; UNKNOWN_SIZE];
+            accessor.copy_to_slice(&mut raw);
+            return Ok(Some(Directory {
+                name: format!("unknown@{offset:#x}"),
+                kind: EntryKind::Unknown,
+                offset,
+                parent_offset: opt.parent_offset,
+                size: 0,
+                checksum: 0,
+                raw: Some(raw),
+            }));
         }
 
         let (kind, name) = if kind == UOL {
@@ -40,9 +100,8 @@ impl TryFromAccessor for Option<Directory> {
             panic!("invalid element kind {kind}");
         };
 
-        let size = accessor.get_var_i32_le();
-        // all the data byte sum together
-        let _checksum = accessor.get_var_i32_le();
+        let size = accessor.get_var_i32_le() as usize;
+        let checksum = accessor.get_var_i32_le();
         let data_offset = compute_offset(&opt, accessor);
 
         match kind {
@@ -51,12 +110,15 @@ impl TryFromAccessor for Option<Directory> {
                     seek_back(accessor, SeekFrom::Start(data_offset as u64), |accessor| {
                         Image::try_from_accessor(opt.clone_with(data_offset), accessor)
                     })?;
-                image.size = size as usize;
+                image.size = size;
                 Ok(Some(Directory {
                     name,
                     kind: image.kind,
                     offset: image.offset,
                     parent_offset: data_offset,
+                    size,
+                    checksum,
+                    raw: None,
                 }))
             }
             FOLDER => Ok(Some(Directory {
@@ -64,6 +126,9 @@ impl TryFromAccessor for Option<Directory> {
                 kind: EntryKind::Folder,
                 offset: data_offset,
                 parent_offset: opt.parent_offset,
+                size,
+                checksum,
+                raw: None,
             })),
             _ => Err(Error::UnexpectedData(format!(
                 "unexpected element kind {kind}"
@@ -112,6 +177,7 @@ impl TryFromAccessor for Directories {
     }
 }
 
+/// offsets are encrypted in 32-bit, only the low 32 bits of offset is stored.
 #[inline]
 fn compute_offset(opt: &AccessorOpt, accessor: &mut dyn Accessor) -> usize {
     let hash = opt.ver_hash as u32;
@@ -121,6 +187,166 @@ fn compute_offset(opt: &AccessorOpt, accessor: &mut dyn Accessor) -> usize {
         .wrapping_sub(0x581c3f6d);
     let factor = offset & 0x1f;
     let enc_offset = accessor.get_u32_le();
-    ((offset.wrapping_shl(factor) | offset.wrapping_shr(32 - factor)) ^ enc_offset)
-        .wrapping_add((data_pos as u32).wrapping_shl(1)) as usize
+    let low = ((offset.wrapping_shl(factor) | offset.wrapping_shr(32 - factor)) ^ enc_offset)
+        .wrapping_add((data_pos as u32).wrapping_shl(1));
+    extend_offset(accessor.pos() as u64, accessor.len() as u64, low) as usize
+}
+
+/// data always placed after the directory table referring it, so the offset
+/// is the first one within file not before `pos` which has the `low` 32 bits.
+#[inline]
+fn extend_offset(pos: u64, len: u64, low: u32) -> u64 {
+    let offset = (pos & !(u32::MAX as u64)) | low as u64;
+    if offset < pos && offset + (1 << 32) < len {
+        offset + (1 << 32)
+    } else {
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::MapleTableNone;
+    use crate::reader::BinaryAccessor;
+
+    #[test]
+    fn unknown_record_named_by_offset() {
+        let mut data = vec![0xff; 4];
+        data.push(UNKNOWN);
+        data.extend(1..=UNKNOWN_SIZE as u8);
+        let mut accessor = BinaryAccessor::new(MapleTableNone, data);
+        accessor.seek(SeekFrom::Start(4));
+        let dir = Option::<Directory>::try_from_accessor(AccessorOpt::default(), &mut accessor)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dir.name, "unknown@0x5");
+        assert_eq!(dir.kind, EntryKind::Unknown);
+        assert_eq!(dir.raw, Some([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        assert_eq!(dir.data_offset(), None);
+    }
+
+    #[test]
+    fn unknown_kind_sorts_last() {
+        let mut kinds = [
+            EntryKind::Unknown,
+            EntryKind::Property(crate::PropertyKind::Encode),
+            EntryKind::Folder,
+        ];
+        kinds.sort();
+        assert_eq!(kinds.last(), Some(&EntryKind::Unknown));
+        assert_eq!(kinds.first(), Some(&EntryKind::Folder));
+    }
+
+    #[test]
+    fn calc_checksum_of_image_data() {
+        let mut data = vec![0xff; 4];
+        data.extend([1, 2, 3, 0xfe]);
+        data.extend([0xff; 4]);
+        let mut accessor = BinaryAccessor::new(MapleTableNone, data);
+        let mut dir = Directory {
+            name: "0100100.img".to_owned(),
+            kind: EntryKind::Property(crate::PropertyKind::Encode),
+            offset: 4,
+            parent_offset: 4,
+            size: 4,
+            checksum: 1 + 2 + 3 + 0xfe,
+            raw: None,
+        };
+        assert_eq!(dir.calc_checksum(&mut accessor).unwrap(), 0x104);
+        assert!(dir.verify(&mut accessor).unwrap());
+        // the accessor is left where it was
+        assert_eq!(accessor.pos(), 0);
+
+        dir.checksum += 1;
+        assert!(!dir.verify(&mut accessor).unwrap());
+        dir.size = 9;
+        assert!(matches!(
+            dir.calc_checksum(&mut accessor),
+            Err(Error::BrokenFile)
+        ));
+
+        // folders have no data to sum
+        dir.kind = EntryKind::Folder;
+        assert_eq!(dir.calc_checksum(&mut accessor).unwrap(), 0);
+        assert!(dir.verify(&mut accessor).unwrap());
+    }
+
+    const GIB4: u64 = 1 << 32;
+
+    #[test]
+    fn extend_offset_below_boundary() {
+        let len = GIB4 + 0x10000;
+        // data ahead of pos within the same 4 GiB
+        assert_eq!(extend_offset(GIB4 - 0x10, len, 0xffff_fff8), 0xffff_fff8);
+        // low bits before pos wrap to the next 4 GiB
+        assert_eq!(extend_offset(GIB4 - 0x10, len, 0x20), GIB4 + 0x20);
+        // unless it is beyond the file
+        assert_eq!(extend_offset(GIB4 - 0x10, GIB4, 0x20), 0x20);
+        assert_eq!(extend_offset(0x100, 0x1000, 0x80), 0x80);
+    }
+
+    #[test]
+    fn extend_offset_above_boundary() {
+        let len = 2 * GIB4 + 0x10000;
+        assert_eq!(extend_offset(GIB4, len, 0), GIB4);
+        assert_eq!(extend_offset(GIB4 + 0x10, len, 0x20), GIB4 + 0x20);
+        assert_eq!(extend_offset(GIB4 + 0x10, len, 0x8), 2 * GIB4 + 0x8);
+        assert_eq!(extend_offset(GIB4 + 0x10, GIB4 + 0x1000, 0x8), GIB4 + 0x8);
+        assert_eq!(
+            extend_offset(GIB4 + 0x10, len, 0xffff_fff0),
+            GIB4 + 0xffff_fff0
+        );
+    }
+
+    /// folder record named `Map` at `pos` refers to `target`.
+    fn folder_record(opt: &AccessorOpt, pos: u64, target: u64) -> Vec<u8> {
+        let mut record = vec![FOLDER, -3i8 as u8];
+        record.extend(b"Map".iter().enumerate().map(|(i, b)| b ^ (0xaa + i as u8)));
+        // size and checksum
+        record.extend([5, 7]);
+        let enc_pos = pos + record.len() as u64;
+        let key = ((enc_pos - opt.parent_offset as u64) as u32 ^ u32::MAX)
+            .wrapping_mul(opt.ver_hash as u32)
+            .wrapping_sub(0x581c3f6d);
+        let low = (target as u32).wrapping_sub((opt.parent_offset as u32).wrapping_shl(1));
+        record.extend((key.rotate_left(key & 0x1f) ^ low).to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn folder_record_beyond_4gib() {
+        use std::io::{Seek, Write};
+
+        let dir = crate::reader::pack::tests::temp_dir("directory-4gib");
+        let path = dir.join("Sparse.wz");
+        let opt = AccessorOpt {
+            offset: 0,
+            ver_hash: 0x1234,
+            parent_offset: 0x3c,
+        };
+        let target = GIB4 + 0x2000;
+        let positions = [GIB4 - 0x100, GIB4 + 0x100];
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.set_len(GIB4 + 0x10000).unwrap();
+        for pos in positions {
+            file.seek(SeekFrom::Start(pos)).unwrap();
+            file.write_all(&folder_record(&opt, pos, target)).unwrap();
+        }
+        drop(file);
+
+        let data = crate::reader::Source::new(&path).open().unwrap();
+        let mut accessor = BinaryAccessor::new(MapleTableNone, data);
+        for pos in positions {
+            accessor.seek(SeekFrom::Start(pos));
+            let dir = Option::<Directory>::try_from_accessor(opt, &mut accessor)
+                .unwrap()
+                .unwrap();
+            assert_eq!(dir.name, "Map");
+            assert_eq!(dir.kind, EntryKind::Folder);
+            assert_eq!((dir.size, dir.checksum), (5, 7));
+            assert_eq!(dir.offset as u64, target);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
 
+pub(crate) const UNKNOWN: &str = "Unknown";
 pub(crate) const FOLDER: &str = "Folder";
 pub(crate) const PROPERTY: &str = "Property";
 pub(crate) const CANVAS: &str = "Canvas";
@@ -34,6 +35,8 @@ pub enum EntryKind {
     Folder,
     Image(ImageKind),
     Property(PropertyKind),
+    /// directory record of unknown kind, see `Directory::raw`.
+    Unknown,
 }
 
 impl EntryKind {
@@ -51,6 +54,7 @@ impl EntryKind {
                 ImageKind::RawData => RAW_DATA,
                 ImageKind::Script => SCRIPT,
             },
+            EntryKind::Unknown => UNKNOWN,
         }
     }
 }
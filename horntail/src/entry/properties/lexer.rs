@@ -29,6 +29,9 @@ impl<'a> Iterator for Lexer<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut last = self.rest;
+        // bytes read since `last` and up to the end of the last word, spaces
+        // between words are kept.
+        let mut read = 0;
         let mut words = 0;
         for c in self.rest.chars() {
             self.rest = &self.rest[c.len_utf8()..];
@@ -38,12 +41,13 @@ impl<'a> Iterator for Lexer<'a> {
                 '}' => TokenKind::RightBrace,
                 NEW_LINE => TokenKind::EndOfLine,
                 _ => {
-                    if c.is_whitespace() {
-                        if words == 0 {
-                            last = &last[c.len_utf8()..];
-                        }
-                    } else {
-                        words += c.len_utf8();
+                    if c.is_whitespace() && words == 0 {
+                        last = &last[c.len_utf8()..];
+                        continue;
+                    }
+                    read += c.len_utf8();
+                    if !c.is_whitespace() {
+                        words = read;
                     }
                     continue;
                 }
@@ -57,3 +61,16 @@ impl<'a> Iterator for Lexer<'a> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_with_spaces() {
+        let tokens = Lexer::new("  name  =  Borrowing the  Mirror \r\n")
+            .map(|t| t.origin)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, ["name", "Borrowing the  Mirror"]);
+    }
+}
@@ -2,7 +2,7 @@ use crate::reader::Accessor;
 use crate::{AccessorOpt, Error, Image, TryFromAccessor};
 use std::io::SeekFrom;
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub struct Vector2D {
     pub x: i32,
     pub y: i32,
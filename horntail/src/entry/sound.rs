@@ -1,5 +1,5 @@
-use crate::reader::Accessor;
-use crate::{AccessorOpt, Error, Properties, TryFromAccessor};
+use crate::reader::{Accessor, BinaryAccessor};
+use crate::{AccessorOpt, Error, Properties, TryFromAccessor, TryFromBinary};
 use std::time::Duration;
 
 #[derive(Clone)]
@@ -138,6 +138,46 @@ impl TryFromAccessor for Sound {
     }
 }
 
+/// sound with data borrowed from the mapped slice.
+#[derive(Clone)]
+pub struct SoundRef<'a> {
+    attr: SoundAttribute,
+    raw_data: &'a [u8],
+}
+
+impl<'a> SoundRef<'a> {
+    #[inline]
+    pub fn attr(&self) -> &SoundAttribute {
+        &self.attr
+    }
+
+    #[inline]
+    pub fn raw_data(&self) -> &'a [u8] {
+        self.raw_data
+    }
+
+    #[inline]
+    pub fn into_sound(self) -> Sound {
+        Sound {
+            attr: self.attr,
+            raw_data: self.raw_data.to_vec(),
+        }
+    }
+}
+
+impl<'a> TryFromBinary<'a> for SoundRef<'a> {
+    type Error = Error;
+
+    fn try_from_binary(
+        opt: AccessorOpt,
+        accessor: &mut BinaryAccessor<&'a [u8]>,
+    ) -> Result<Self, Self::Error> {
+        let attr = SoundAttribute::try_from_accessor(opt, accessor)?;
+        let raw_data = accessor.try_borrow(attr.data_size)?;
+        Ok(SoundRef { attr, raw_data })
+    }
+}
+
 #[inline]
 fn parse_wave_format(accessor: &mut dyn Accessor) -> crate::error::Result<WaveFormat> {
     let fmt_len = accessor.get_var_i32_le() as usize;
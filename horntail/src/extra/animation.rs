@@ -0,0 +1,477 @@
+#[cfg(feature = "image")]
+use crate::PixelLayout;
+use crate::extra::iter::{ComponentIter, join_relative};
+use crate::extra::{EntryCache, EntryValue};
+use crate::{Canvas, EntryKind, Error, ImageKind, UOL, Vector2D};
+use std::path::Path;
+use std::time::Duration;
+
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+const OPAQUE: u8 = u8::MAX;
+/// fading frames are split into steps of this duration on export.
+#[cfg(feature = "image")]
+const FADE_STEP: Duration = Duration::from_millis(30);
+
+pub struct Frame {
+    pub name: String,
+    pub canvas: Canvas,
+    pub delay: Duration,
+    pub origin: Vector2D,
+    pub z: i32,
+    /// alpha at the start of frame.
+    pub a0: u8,
+    /// alpha at the end of frame.
+    pub a1: u8,
+    pub lt: Option<Vector2D>,
+    pub rb: Option<Vector2D>,
+}
+
+impl Frame {
+    /// `placeholder` is the numbered child, `canvas` is the one it links to.
+    fn try_from_entries(placeholder: &EntryCache, canvas: &EntryCache) -> Result<Frame, Error> {
+        let prop = |name: &str| -> Result<Option<&EntryCache>, Error> {
+            Ok(match placeholder.try_get(name)? {
+                Some(e) => Some(e),
+                None => canvas.try_get(name)?,
+            })
+        };
+
+        let delay = match prop("delay")? {
+            Some(e) => Duration::from_millis(e.try_to::<i64>()?.max(0) as u64),
+            None => DEFAULT_DELAY,
+        };
+        let a0 = match prop("a0")? {
+            Some(e) => e.try_to::<i32>()?.clamp(0, OPAQUE as i32) as u8,
+            None => OPAQUE,
+        };
+        let a1 = match prop("a1")? {
+            Some(e) => e.try_to::<i32>()?.clamp(0, OPAQUE as i32) as u8,
+            None => a0,
+        };
+
+        Ok(Frame {
+            name: placeholder.name().to_owned(),
+            canvas: canvas.try_to()?,
+            delay,
+            origin: prop("origin")?
+                .map(|e| e.try_to::<Vector2D>())
+                .transpose()?
+                .unwrap_or_default(),
+            // some sprites use names like `arm` as z
+            z: prop("z")?
+                .and_then(|e| e.try_to::<i32>().ok())
+                .unwrap_or_default(),
+            a0,
+            a1,
+            lt: prop("lt")?.map(|e| e.try_to::<Vector2D>()).transpose()?,
+            rb: prop("rb")?.map(|e| e.try_to::<Vector2D>()).transpose()?,
+        })
+    }
+
+    /// alpha after `elapsed` in this frame, interpolated from `a0` to `a1`.
+    pub fn alpha_at(&self, elapsed: Duration) -> u8 {
+        if self.a0 == self.a1 || self.delay.is_zero() {
+            return self.a0;
+        }
+        let progress = elapsed.min(self.delay).as_secs_f64() / self.delay.as_secs_f64();
+        let (a0, a1) = (self.a0 as f64, self.a1 as f64);
+        (a0 + (a1 - a0) * progress).round() as u8
+    }
+
+    /// position of canvas left top relative to the origin.
+    #[inline]
+    fn left_top(&self) -> (i32, i32) {
+        (-self.origin.x, -self.origin.y)
+    }
+}
+
+/// frames of numbered child canvases `0`, `1`, ... in order.
+pub struct Animation {
+    frames: Vec<Frame>,
+}
+
+impl Animation {
+    /// load animation at `path` from `root`, canvas links and `UOL` frames
+    /// are resolved from `root` too.
+    pub fn try_resolve<P: AsRef<Path>>(root: &EntryCache, path: P) -> Result<Animation, Error> {
+        let base = ComponentIter::from(path.as_ref().components())
+            .map(|c| c.to_owned())
+            .collect::<Vec<_>>();
+        let entry = root
+            .try_get_by_path(base.join("/"))?
+            .ok_or(Error::InvalidArgument)?;
+        Self::load(entry, |name, uol| {
+            let components = match uol {
+                Some(uol) => join_relative(&base, &uol.path),
+                None => Some([base.as_slice(), &[name.to_owned()]].concat()),
+            };
+            match components {
+                Some(components) => root.try_resolve_canvas(components.join("/")),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn load<'a, F>(entry: &'a EntryCache, mut resolve: F) -> Result<Animation, Error>
+    where
+        F: FnMut(&str, Option<&UOL>) -> Result<Option<&'a EntryCache>, Error>,
+    {
+        let mut children = entry
+            .try_iter()?
+            .filter_map(|e| e.name().parse::<usize>().ok().map(|i| (i, e)))
+            .collect::<Vec<_>>();
+        children.sort_by_key(|(i, _)| *i);
+
+        let mut frames = Vec::with_capacity(children.len());
+        for (_, child) in children {
+            let canvas = match child.value() {
+                EntryValue::Kind(EntryKind::Image(ImageKind::Canvas)) => {
+                    resolve(child.name(), None)?
+                }
+                EntryValue::Kind(EntryKind::Image(ImageKind::UOL)) => {
+                    resolve(child.name(), Some(&child.try_to::<UOL>()?))?
+                }
+                _ => continue,
+            };
+            // dangling links are dropped
+            let Some(canvas) = canvas else {
+                continue;
+            };
+            frames.push(Frame::try_from_entries(child, canvas)?);
+        }
+        Ok(Animation { frames })
+    }
+
+    #[inline]
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> Vec<Frame> {
+        self.frames
+    }
+
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|f| f.delay).sum()
+    }
+
+    /// frame and elapsed time in it at `time` of a single loop.
+    pub fn frame_at(&self, time: Duration) -> Option<(&Frame, Duration)> {
+        let mut start = Duration::ZERO;
+        for frame in self.frames.iter() {
+            if time < start + frame.delay {
+                return Some((frame, time - start));
+            }
+            start += frame.delay;
+        }
+        None
+    }
+
+    /// left top and right bottom covers all frames, relative to origin.
+    pub fn bounds(&self) -> Option<(Vector2D, Vector2D)> {
+        self.frames.iter().fold(None, |bounds, frame| {
+            let (x, y) = frame.left_top();
+            let (r, b) = (x + frame.canvas.attr.size.x, y + frame.canvas.attr.size.y);
+            Some(match bounds {
+                None => (Vector2D { x, y }, Vector2D { x: r, y: b }),
+                Some((lt, rb)) => (
+                    Vector2D {
+                        x: lt.x.min(x),
+                        y: lt.y.min(y),
+                    },
+                    Vector2D {
+                        x: rb.x.max(r),
+                        y: rb.y.max(b),
+                    },
+                ),
+            })
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a EntryCache> for Animation {
+    type Error = Error;
+
+    /// canvas links are not followed, `UOL` frames are resolved inside `entry`.
+    fn try_from(entry: &'a EntryCache) -> Result<Self, Self::Error> {
+        Self::load(entry, |name, uol| match uol {
+            Some(uol) => match join_relative(&[], &uol.path) {
+                Some(components) => entry.try_get_by_path(components.join("/")),
+                None => Ok(None),
+            },
+            None => entry.try_get(name),
+        })
+    }
+}
+
+/// rgba frames with same size.
+#[cfg(feature = "image")]
+struct Composed {
+    width: u32,
+    height: u32,
+    frames: Vec<(Duration, Vec<u8>)>,
+}
+
+#[cfg(feature = "image")]
+impl Animation {
+    /// frames drawn on a common canvas with origins aligned, fading frames
+    /// are split into steps.
+    fn compose(&self) -> Result<Composed, Error> {
+        let (lt, rb) = self.bounds().ok_or(Error::InvalidArgument)?;
+        let (width, height) = ((rb.x - lt.x) as usize, (rb.y - lt.y) as usize);
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidArgument);
+        }
+
+        let mut composed = vec![];
+        for frame in self.frames.iter() {
+            let mut pixels = vec![0; frame.canvas.decoded_size()];
+            frame.canvas.decode_into(&mut pixels, PixelLayout::Rgba8)?;
+            let (x, y) = frame.left_top();
+            let (x, y) = ((x - lt.x) as usize, (y - lt.y) as usize);
+            let frame_width = (frame.canvas.attr.size.x as usize).max(1);
+
+            let mut elapsed = Duration::ZERO;
+            loop {
+                let step = if frame.a0 == frame.a1 {
+                    frame.delay
+                } else {
+                    FADE_STEP.min(frame.delay - elapsed)
+                };
+                let alpha = frame.alpha_at(elapsed) as u32;
+                let mut buffer = vec![0u8; width * height * PixelLayout::BYTES_PER_PIXEL];
+                pixels
+                    .chunks_exact(frame_width * PixelLayout::BYTES_PER_PIXEL)
+                    .enumerate()
+                    .for_each(|(row, src)| {
+                        let off = ((y + row) * width + x) * PixelLayout::BYTES_PER_PIXEL;
+                        let dst = &mut buffer[off..off + src.len()];
+                        dst.copy_from_slice(src);
+                        dst.chunks_exact_mut(PixelLayout::BYTES_PER_PIXEL)
+                            .for_each(|p| p[3] = (p[3] as u32 * alpha / OPAQUE as u32) as u8);
+                    });
+                composed.push((step, buffer));
+                elapsed += step;
+                if elapsed >= frame.delay {
+                    break;
+                }
+            }
+        }
+        Ok(Composed {
+            width: width as u32,
+            height: height as u32,
+            frames: composed,
+        })
+    }
+
+    /// looped APNG.
+    pub fn to_apng(&self) -> Result<Vec<u8>, Error> {
+        let Composed {
+            width,
+            height,
+            frames,
+        } = self.compose()?;
+        let mut buf = Vec::new();
+        let mut encoder = png::Encoder::new(&mut buf, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, 0)
+            .map_err(|e| Error::Unexpected(Box::new(e)))?;
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| Error::Unexpected(Box::new(e)))?;
+        for (delay, data) in frames.iter() {
+            let delay = delay.as_millis().min(u16::MAX as u128) as u16;
+            writer
+                .set_frame_delay(delay, 1000)
+                .and_then(|_| writer.write_image_data(data))
+                .map_err(|e| Error::Unexpected(Box::new(e)))?;
+        }
+        writer
+            .finish()
+            .map_err(|e| Error::Unexpected(Box::new(e)))?;
+        Ok(buf)
+    }
+
+    /// looped GIF, alpha is reduced to 1 bit by the format.
+    pub fn to_gif(&self) -> Result<Vec<u8>, Error> {
+        use image::codecs::gif::{GifEncoder, Repeat};
+        use image::{Delay, RgbaImage};
+
+        let Composed {
+            width,
+            height,
+            frames,
+        } = self.compose()?;
+        let mut buf = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut buf);
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .map_err(|e| Error::Unexpected(Box::new(e)))?;
+            for (delay, data) in frames {
+                let image =
+                    RgbaImage::from_raw(width, height, data).ok_or(Error::InvalidArgument)?;
+                encoder
+                    .encode_frame(image::Frame::from_parts(
+                        image,
+                        0,
+                        0,
+                        Delay::from_saturating_duration(delay),
+                    ))
+                    .map_err(|e| Error::Unexpected(Box::new(e)))?;
+            }
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::pack::tests::encoded_root;
+    use crate::reader::wizet::tests::TestValue::{self, Int, Props, Str, Uol, Vector};
+
+    const PATH: &str = "Mob/0100100.img/stand";
+
+    fn canvas(width: i32, props: Vec<(&'static str, TestValue)>) -> TestValue {
+        TestValue::Canvas {
+            size: (width, 1),
+            format: 2,
+            data: [0x10, 0x20, 0x30, OPAQUE].repeat(width as usize),
+            props,
+        }
+    }
+
+    fn stand() -> TestValue {
+        Props(vec![(
+            "stand",
+            Props(vec![
+                ("10", canvas(2, vec![("delay", Int(50))])),
+                (
+                    "2",
+                    canvas(
+                        1,
+                        vec![
+                            ("delay", Int(90)),
+                            ("origin", Vector(1, 1)),
+                            ("a0", Int(255)),
+                            ("a1", Int(0)),
+                        ],
+                    ),
+                ),
+                ("0", canvas(1, vec![("delay", Int(200)), ("z", Str("arm"))])),
+                ("1", Uol("0")),
+                ("3", canvas(1, vec![("_inlink", Str("stand/9"))])),
+                ("x", Int(1)),
+            ]),
+        )])
+    }
+
+    fn names(animation: &Animation) -> Vec<&str> {
+        animation.frames().iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn frames_in_order() {
+        let (dir, root) = encoded_root("animation-frames", &[("Mob/0100100.img", stand())]);
+        let animation = Animation::try_resolve(&root, PATH).unwrap();
+        // dangling links are dropped
+        assert_eq!(names(&animation), ["0", "1", "2", "10"]);
+        let frames = animation.frames();
+        assert_eq!(frames[0].z, 0);
+        // `UOL` frames take the properties of the target
+        assert_eq!(frames[1].delay, Duration::from_millis(200));
+        assert_eq!(frames[2].origin, Vector2D { x: 1, y: 1 });
+        assert_eq!((frames[3].a0, frames[3].a1), (OPAQUE, OPAQUE));
+        assert_eq!(animation.duration(), Duration::from_millis(540));
+
+        // links are not followed inside the entry
+        let stand = root.get_by_path(PATH).unwrap();
+        let animation = Animation::try_from(stand).unwrap();
+        assert_eq!(names(&animation), ["0", "1", "2", "3", "10"]);
+        assert!(matches!(
+            Animation::try_resolve(&root, "Mob/0100100.img/walk"),
+            Err(Error::InvalidArgument)
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn timing_and_bounds() {
+        let (dir, root) = encoded_root("animation-timing", &[("Mob/0100100.img", stand())]);
+        let animation = Animation::try_resolve(&root, PATH).unwrap();
+        let at = |ms| {
+            animation
+                .frame_at(Duration::from_millis(ms))
+                .map(|(f, elapsed)| (f.name.as_str(), elapsed.as_millis()))
+        };
+        assert_eq!(at(0), Some(("0", 0)));
+        assert_eq!(at(250), Some(("1", 50)));
+        assert_eq!(at(445), Some(("2", 45)));
+        assert_eq!(at(539), Some(("10", 49)));
+        assert_eq!(at(540), None);
+
+        let fade = &animation.frames()[2];
+        let alpha = |ms| fade.alpha_at(Duration::from_millis(ms));
+        assert_eq!(
+            [alpha(0), alpha(30), alpha(45), alpha(90)],
+            [255, 170, 128, 0]
+        );
+        assert_eq!(alpha(200), 0);
+        assert_eq!(
+            animation.frames()[0].alpha_at(Duration::from_millis(100)),
+            OPAQUE
+        );
+
+        assert_eq!(
+            animation.bounds(),
+            Some((Vector2D { x: -1, y: -1 }, Vector2D { x: 2, y: 1 }))
+        );
+        assert_eq!(Animation { frames: vec![] }.bounds(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn export_fade_steps() {
+        use image::AnimationDecoder;
+        use image::codecs::gif::GifDecoder;
+        use std::io::Cursor;
+
+        let (dir, root) = encoded_root("animation-export", &[("Mob/0100100.img", stand())]);
+        let animation = Animation::try_resolve(&root, PATH).unwrap();
+        // three frames and the 90ms fade in steps of 30ms
+        let steps = 3 + 3;
+
+        let apng = animation.to_apng().unwrap();
+        let mut reader = png::Decoder::new(Cursor::new(apng)).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, steps);
+        assert_eq!((reader.info().width, reader.info().height), (3, 2));
+        let mut buffer = vec![0; reader.output_buffer_size().unwrap()];
+        let mut alphas = vec![];
+        for _ in 0..steps {
+            reader.next_frame(&mut buffer).unwrap();
+            // left top of the fading frame, right bottom of the others
+            alphas.push((buffer[3], buffer[buffer.len() - 1]));
+        }
+        assert_eq!(
+            alphas,
+            [(0, 0), (0, 0), (255, 0), (170, 0), (85, 0), (0, 0xff),]
+        );
+
+        let gif = animation.to_gif().unwrap();
+        let frames = GifDecoder::new(Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), steps as usize);
+        assert_eq!(frames[2].delay().numer_denom_ms(), (30, 1));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
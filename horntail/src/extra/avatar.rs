@@ -0,0 +1,379 @@
+use crate::extra::EntryCache;
+use crate::{Canvas, Error, Vector2D};
+#[cfg(feature = "image")]
+use image::{Rgba, RgbaImage, imageops};
+
+const CHARACTER: &str = "Character";
+const BODY: &str = "body";
+const ZMAP: &str = "zmap.img";
+const SMAP: &str = "smap.img";
+const DEFAULT_EXPRESSION: &str = "default";
+
+/// items of a character, ids are the same as image names in `Character`.
+#[derive(Debug, Clone)]
+pub struct Avatar {
+    pub body: u32,
+    pub head: u32,
+    pub face: Option<u32>,
+    pub hair: Option<u32>,
+    pub equips: Vec<u32>,
+    /// e.g. `stand1`, `walk1`
+    pub action: String,
+    pub frame: usize,
+    /// face expression e.g. `default`, `blink`
+    pub expression: String,
+}
+
+impl Default for Avatar {
+    fn default() -> Self {
+        Self {
+            body: 2000,
+            head: 12000,
+            face: None,
+            hair: None,
+            equips: vec![],
+            action: "stand1".to_owned(),
+            frame: 0,
+            expression: DEFAULT_EXPRESSION.to_owned(),
+        }
+    }
+}
+
+/// a positioned part of avatar.
+pub struct AvatarLayer {
+    pub item: u32,
+    /// part name, e.g. `body`, `arm`, `hairOverHead`
+    pub name: String,
+    pub z: String,
+    pub canvas: Canvas,
+    /// left top of canvas relative to the body origin.
+    pub position: Vector2D,
+}
+
+struct Part {
+    item: u32,
+    name: String,
+    z: String,
+    canvas: Canvas,
+    origin: Vector2D,
+    map: Vec<(String, Vector2D)>,
+}
+
+/// compose characters from `Character` with `zmap.img` and `smap.img` of `Base`.
+pub struct AvatarRenderer<'a> {
+    root: &'a EntryCache,
+    /// layer names from the front to the back.
+    zmap: Vec<String>,
+    smap: ahash::HashMap<String, String>,
+}
+
+impl<'a> AvatarRenderer<'a> {
+    /// `root` is the `Base` bundle root holds `Character`, `zmap.img` and `smap.img`.
+    pub fn try_new(root: &'a EntryCache) -> Result<Self, Error> {
+        // rank is the order on disk, the cache doesn't keep it
+        let zmap = root
+            .try_get(ZMAP)?
+            .ok_or(Error::InvalidArgument)?
+            .as_ref()
+            .try_iter()?
+            .map(|e| e.name().to_owned())
+            .collect::<Vec<_>>();
+        let mut smap = ahash::HashMap::default();
+        for e in root
+            .try_get(SMAP)?
+            .ok_or(Error::InvalidArgument)?
+            .try_iter()?
+        {
+            smap.insert(e.name().to_owned(), e.try_to::<String>()?);
+        }
+        Ok(AvatarRenderer { root, zmap, smap })
+    }
+
+    /// parts of `avatar` aligned by their `map` vectors, ordered from the
+    /// back to the front.
+    pub fn layers(&self, avatar: &Avatar) -> Result<Vec<AvatarLayer>, Error> {
+        let mut parts = self.parts(avatar.body, &avatar.action, avatar.frame)?;
+        if parts.is_empty() {
+            return Err(Error::InvalidArgument);
+        }
+        // `body` is the anchor of all the other parts
+        if let Some(i) = parts.iter().position(|p| p.name == BODY) {
+            parts.swap(0, i);
+        }
+        parts.extend(self.parts(avatar.head, &avatar.action, avatar.frame)?);
+        if let Some(face) = avatar.face {
+            parts.extend(self.face_parts(face, &avatar.expression)?);
+        }
+        if let Some(hair) = avatar.hair {
+            parts.extend(self.parts(hair, &avatar.action, avatar.frame)?);
+        }
+
+        let mut vslots = vec![];
+        for equip in avatar.equips.iter() {
+            parts.extend(self.parts(*equip, &avatar.action, avatar.frame)?);
+            if let Some(vslot) = self.vslot(*equip)? {
+                vslots.push((*equip, vslot));
+            }
+        }
+
+        // hide parts covered by equips, like hair under a cap
+        parts.retain(|part| {
+            let Some(islot) = self.smap.get(&part.z).filter(|s| !s.is_empty()) else {
+                return true;
+            };
+            !vslots.iter().any(|(item, vslot)| {
+                *item != part.item && slots(islot).all(|s| slots(vslot).any(|v| v == s))
+            })
+        });
+
+        let mut layers = align(parts);
+        layers.sort_by_key(|layer| {
+            let index = self.zmap.iter().position(|z| *z == layer.z);
+            std::cmp::Reverse(index.unwrap_or(self.zmap.len()))
+        });
+        Ok(layers)
+    }
+
+    fn item_path(id: u32) -> Option<Vec<String>> {
+        let folder = match id / 10000 {
+            0 | 1 => None,
+            2 | 5 => Some("Face"),
+            3 | 4 | 6 => Some("Hair"),
+            100 => Some("Cap"),
+            101..=103 | 112..=119 => Some("Accessory"),
+            104 => Some("Coat"),
+            105 => Some("Longcoat"),
+            106 => Some("Pants"),
+            107 => Some("Shoes"),
+            108 => Some("Glove"),
+            109 => Some("Shield"),
+            110 => Some("Cape"),
+            111 => Some("Ring"),
+            121..=170 => Some("Weapon"),
+            _ => return None,
+        };
+        let mut path = vec![CHARACTER.to_owned()];
+        path.extend(folder.map(|f| f.to_owned()));
+        path.push(format!("{id:08}.img"));
+        Some(path)
+    }
+
+    fn vslot(&self, id: u32) -> Result<Option<String>, Error> {
+        let Some(mut path) = Self::item_path(id) else {
+            return Ok(None);
+        };
+        path.extend(["info".to_owned(), "vslot".to_owned()]);
+        match self.root.try_get_by_path(path.join("/"))? {
+            Some(e) => Ok(Some(e.try_to::<String>()?)),
+            None => Ok(None),
+        }
+    }
+
+    fn parts(&self, id: u32, action: &str, frame: usize) -> Result<Vec<Part>, Error> {
+        let Some(mut path) = Self::item_path(id) else {
+            return Ok(vec![]);
+        };
+        path.extend([action.to_owned(), frame.to_string()]);
+        self.collect_parts(id, path)
+    }
+
+    /// `default` expression holds parts directly, others hold frames.
+    fn face_parts(&self, id: u32, expression: &str) -> Result<Vec<Part>, Error> {
+        let Some(mut path) = Self::item_path(id) else {
+            return Ok(vec![]);
+        };
+        path.push(expression.to_owned());
+        if expression != DEFAULT_EXPRESSION {
+            path.push("0".to_owned());
+        }
+        self.collect_parts(id, path)
+    }
+
+    fn collect_parts(&self, item: u32, path: Vec<String>) -> Result<Vec<Part>, Error> {
+        let Some(entry) = self.root.try_get_by_path(path.join("/"))? else {
+            return Ok(vec![]);
+        };
+        let mut parts = vec![];
+        for child in entry.try_iter()? {
+            let mut components = path.clone();
+            components.push(child.name().to_owned());
+            let Some(canvas) = self.root.try_resolve_part(components)? else {
+                continue;
+            };
+            parts.push(Part::try_from_entries(item, child, canvas)?);
+        }
+        Ok(parts)
+    }
+
+    /// composed image and the body origin in it.
+    #[cfg(feature = "image")]
+    pub fn render(&self, avatar: &Avatar) -> Result<(RgbaImage, Vector2D), Error> {
+        let layers = self.layers(avatar)?;
+        let (mut lt, mut rb) = (Vector2D::default(), Vector2D::default());
+        for layer in layers.iter() {
+            lt.x = lt.x.min(layer.position.x);
+            lt.y = lt.y.min(layer.position.y);
+            rb.x = rb.x.max(layer.position.x + layer.canvas.attr.size.x);
+            rb.y = rb.y.max(layer.position.y + layer.canvas.attr.size.y);
+        }
+
+        let mut image =
+            RgbaImage::from_pixel((rb.x - lt.x) as u32, (rb.y - lt.y) as u32, Rgba([0; 4]));
+        for layer in layers.iter() {
+            let Some(part) = layer.canvas.image() else {
+                continue;
+            };
+            imageops::overlay(
+                &mut image,
+                &part,
+                (layer.position.x - lt.x) as i64,
+                (layer.position.y - lt.y) as i64,
+            );
+        }
+        Ok((image, Vector2D { x: -lt.x, y: -lt.y }))
+    }
+}
+
+impl Part {
+    /// `placeholder` is the part entry, `canvas` is the one it links to.
+    fn try_from_entries(
+        item: u32,
+        placeholder: &EntryCache,
+        canvas: &EntryCache,
+    ) -> Result<Part, Error> {
+        let prop = |name: &str| -> Result<Option<&EntryCache>, Error> {
+            Ok(match placeholder.try_get(name)? {
+                Some(e) => Some(e),
+                None => canvas.try_get(name)?,
+            })
+        };
+
+        let mut map = vec![];
+        if let Some(vectors) = prop("map")? {
+            for v in vectors.try_iter()? {
+                map.push((v.name().to_owned(), v.try_to::<Vector2D>()?));
+            }
+            // stable alignment regardless of hash order
+            map.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        Ok(Part {
+            item,
+            name: placeholder.name().to_owned(),
+            z: prop("z")?
+                .map(|e| e.try_to::<String>())
+                .transpose()?
+                .unwrap_or_default(),
+            canvas: canvas.try_to()?,
+            origin: prop("origin")?
+                .map(|e| e.try_to::<Vector2D>())
+                .transpose()?
+                .unwrap_or_default(),
+            map,
+        })
+    }
+}
+
+/// 2 characters slot codes.
+#[inline]
+fn slots(s: &str) -> impl Iterator<Item = &str> + '_ {
+    (0..s.len() / 2).filter_map(move |i| s.get(i * 2..i * 2 + 2))
+}
+
+/// place the first part at body origin, then parts share a `map` vector with
+/// placed ones, parts can't be aligned are dropped.
+fn align(parts: Vec<Part>) -> Vec<AvatarLayer> {
+    let mut anchors = ahash::HashMap::<String, Vector2D>::default();
+    let mut layers = Vec::with_capacity(parts.len());
+    let mut pending = parts;
+    let mut first = true;
+    loop {
+        let before = pending.len();
+        let mut rest = vec![];
+        for part in pending {
+            let origin = if first || part.map.is_empty() {
+                Some(Vector2D::default())
+            } else {
+                part.map.iter().find_map(|(name, v)| {
+                    anchors.get(name).map(|a| Vector2D {
+                        x: a.x - v.x,
+                        y: a.y - v.y,
+                    })
+                })
+            };
+            let Some(origin) = origin else {
+                rest.push(part);
+                continue;
+            };
+            first = false;
+            for (name, v) in part.map.iter() {
+                anchors.entry(name.clone()).or_insert(Vector2D {
+                    x: origin.x + v.x,
+                    y: origin.y + v.y,
+                });
+            }
+            layers.push(AvatarLayer {
+                item: part.item,
+                name: part.name,
+                z: part.z,
+                canvas: part.canvas,
+                position: Vector2D {
+                    x: origin.x - part.origin.x,
+                    y: origin.y - part.origin.y,
+                },
+            });
+        }
+        if rest.is_empty() || rest.len() == before {
+            break;
+        }
+        pending = rest;
+    }
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::MapleVersion;
+    use crate::extra::Entry;
+    use crate::reader::PackLayout;
+    use crate::reader::pack::tests::{TestEntry, temp_dir, write_pack};
+
+    #[test]
+    fn zmap_in_disk_order() {
+        let names = (0..32)
+            .map(|i| format!("z{:02}", (i * 7) % 32))
+            .collect::<Vec<_>>();
+        let zmap = names
+            .iter()
+            .fold("#Property\n".to_owned(), |acc, name| acc + name + " = \n");
+        let dir = temp_dir("avatar-zmap");
+        let entries = [
+            TestEntry {
+                name: ZMAP,
+                data: zmap.as_bytes(),
+            },
+            TestEntry {
+                name: SMAP,
+                data: b"#Property\n",
+            },
+        ];
+        write_pack(
+            &dir.join("Base_000.ms"),
+            2,
+            PackLayout::V2,
+            &[],
+            &entries,
+            0,
+        );
+
+        for _ in 0..2 {
+            let root = Entry::from_packs(&dir, MapleVersion::from(95))
+                .unwrap()
+                .into_cache();
+            let renderer = AvatarRenderer::try_new(&root).unwrap();
+            assert_eq!(renderer.zmap, names);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,6 +1,6 @@
 use crate::crypto::{MapleCipher, MapleVersion};
-use crate::reader::BinaryBuilder;
 use crate::reader::wizet::WizetFile;
+use crate::reader::{BinaryBuilder, StringDecoder};
 use crate::{AccessorBuilder, Error};
 use crate::{AccessorOpt, error};
 use std::fs::File;
@@ -15,6 +15,7 @@ pub struct Bundle {
     version: MapleVersion,
     no_version: bool,
     files: Vec<WizetFile>,
+    decoder: StringDecoder,
 }
 
 pub type OptAndBuilder = (AccessorOpt, Box<dyn AccessorBuilder>);
@@ -72,20 +73,32 @@ impl Bundle {
             version,
             no_version,
             files,
+            decoder: StringDecoder::default(),
         }))
     }
 
+    #[inline]
+    pub fn set_string_decoder(&mut self, decoder: StringDecoder) {
+        self.decoder = decoder;
+    }
+
     #[inline]
     pub fn load_by_name(&self, name: &str) -> Option<Bundle> {
         Bundle::from_path(self.dir.join(name), self.version, self.no_version)
             .unwrap_or_else(|e| panic!("load_by_name: {e}"))
+            .map(|mut bundle| {
+                bundle.decoder = self.decoder;
+                bundle
+            })
     }
 
     pub fn builders(&self, cipher: &dyn MapleCipher) -> Result<Vec<OptAndBuilder>, Error> {
         let mut builders = Vec::with_capacity(self.files.len());
         for f in &self.files {
             let mmap = f.source().open()?;
-            let builder = BinaryBuilder::from_boxed(cipher.clone_boxed(), mmap).into_boxed();
+            let builder = BinaryBuilder::from_boxed(cipher.clone_boxed(), mmap)
+                .with_decoder(self.decoder)
+                .into_boxed();
             builders.push((f.accessor_opt(), builder));
         }
         Ok(builders)
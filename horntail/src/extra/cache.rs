@@ -1,9 +1,14 @@
-use crate::Error;
-use crate::extra::iter::ComponentIter;
+use crate::extra::iter::{ComponentIter, join_relative};
 use crate::extra::{Entry, EntryValue};
+use crate::{EntryKind, Error, ImageKind, UOL};
 use std::cell::OnceCell;
 use std::path::Path;
 
+const INLINK: &str = "_inlink";
+const OUTLINK: &str = "_outlink";
+const IMAGE_EXTENSION: &str = ".img";
+const MAX_LINK_DEPTH: usize = 8;
+
 pub struct EntryCache {
     entry: Entry,
     cache: OnceCell<ahash::HashMap<String, EntryCache>>,
@@ -62,6 +67,21 @@ impl EntryCache {
         self.entry.value()
     }
 
+    #[inline]
+    pub fn layer(&self) -> Option<usize> {
+        self.entry.layer()
+    }
+
+    #[inline]
+    pub fn size(&self) -> Option<usize> {
+        self.entry.size()
+    }
+
+    #[inline]
+    pub fn checksum(&self) -> Option<i32> {
+        self.entry.checksum()
+    }
+
     #[inline]
     pub fn has_children(&self) -> bool {
         self.entry.has_children()
@@ -112,6 +132,78 @@ impl EntryCache {
             .unwrap_or_else(|| panic!("path not exists"))
     }
 
+    /// canvas at `path` with `_inlink` and `_outlink` followed, `_inlink` is
+    /// relative to the image contains the canvas, `_outlink` is relative to
+    /// this entry which supposed to be the bundle root. link properties of
+    /// the placeholder are kept as is.
+    pub fn try_resolve_canvas<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<Option<&EntryCache>, Error> {
+        let mut components = ComponentIter::from(path.as_ref().components())
+            .map(|c| c.to_owned())
+            .collect::<Vec<_>>();
+        for _ in 0..MAX_LINK_DEPTH {
+            let Some(canvas) = self.try_get_by_path(components.join("/"))? else {
+                return Ok(None);
+            };
+            if let Some(link) = canvas.try_get_value::<String>(INLINK)? {
+                let Some(image) = components
+                    .iter()
+                    .rposition(|c| c.ends_with(IMAGE_EXTENSION))
+                else {
+                    return Err(Error::UnexpectedData(format!(
+                        "inlink `{link}` outside of image"
+                    )));
+                };
+                components.truncate(image + 1);
+                components.extend(link_components(&link));
+            } else if let Some(link) = canvas.try_get_value::<String>(OUTLINK)? {
+                components = link_components(&link).collect();
+            } else {
+                return Ok(Some(canvas));
+            }
+        }
+        Err(Error::UnexpectedData(format!(
+            "canvas link too deep `{}`",
+            components.join("/")
+        )))
+    }
+
+    #[inline]
+    pub fn resolve_canvas<P: AsRef<Path>>(&self, path: P) -> Option<&EntryCache> {
+        self.try_resolve_canvas(path)
+            .unwrap_or_else(|e| panic!("resolve_canvas: {e}"))
+    }
+
+    /// canvas at `components` with `UOL` and canvas links followed, `None`
+    /// for missing or non canvas entries.
+    pub(crate) fn try_resolve_part(
+        &self,
+        mut components: Vec<String>,
+    ) -> Result<Option<&EntryCache>, Error> {
+        for _ in 0..MAX_LINK_DEPTH {
+            let Some(entry) = self.try_get_by_path(components.join("/"))? else {
+                return Ok(None);
+            };
+            match entry.value() {
+                EntryValue::Kind(EntryKind::Image(ImageKind::Canvas)) => {
+                    return self.try_resolve_canvas(components.join("/"));
+                }
+                EntryValue::Kind(EntryKind::Image(ImageKind::UOL)) => {
+                    let uol = entry.try_to::<UOL>()?;
+                    components.pop();
+                    let Some(joined) = join_relative(&components, &uol.path) else {
+                        return Ok(None);
+                    };
+                    components = joined;
+                }
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
     #[inline]
     pub fn try_to<'a, T: TryFrom<&'a EntryCache, Error = impl Into<Error>>>(
         &'a self,
@@ -198,3 +290,85 @@ impl EntryCache {
         self.try_iter().unwrap_or_else(|e| panic!("iter: {e}"))
     }
 }
+
+#[inline]
+fn link_components(link: &str) -> impl Iterator<Item = String> + '_ {
+    link.split('/')
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::pack::tests::encoded_root;
+    use crate::reader::wizet::tests::TestValue::{self, Int, Props, Str, Uol};
+
+    fn canvas(props: Vec<(&'static str, TestValue)>) -> TestValue {
+        TestValue::Canvas {
+            size: (1, 1),
+            format: 2,
+            data: vec![0, 0, 0, 0xff],
+            props,
+        }
+    }
+
+    fn mark(canvas: Option<&EntryCache>) -> Option<i32> {
+        canvas.and_then(|c| c.try_get_value::<i32>("mark").unwrap())
+    }
+
+    #[test]
+    fn follow_canvas_links() {
+        let (dir, root) = encoded_root(
+            "cache-links",
+            &[
+                (
+                    "Mob/0100100.img",
+                    Props(vec![
+                        (
+                            "stand",
+                            Props(vec![
+                                ("0", canvas(vec![("mark", Int(1))])),
+                                // relative to the image, not the parent
+                                ("1", canvas(vec![(INLINK, Str("stand/0"))])),
+                                ("2", Uol("1")),
+                            ]),
+                        ),
+                        ("loop", canvas(vec![(INLINK, Str("loop"))])),
+                        ("gone", canvas(vec![(INLINK, Str("stand/9"))])),
+                    ]),
+                ),
+                (
+                    "Map/Obj/acc.img",
+                    Props(vec![(
+                        "0",
+                        canvas(vec![(OUTLINK, Str("Mob/0100100.img/stand/1"))]),
+                    )]),
+                ),
+            ],
+        );
+        assert_eq!(
+            mark(root.resolve_canvas("Mob/0100100.img/stand/0")),
+            Some(1)
+        );
+        assert_eq!(
+            mark(root.resolve_canvas("Mob/0100100.img/stand/1")),
+            Some(1)
+        );
+        // `_outlink` from the root followed by an `_inlink`
+        assert_eq!(mark(root.resolve_canvas("Map/Obj/acc.img/0")), Some(1));
+        let part = ["Mob", "0100100.img", "stand", "2"].map(|c| c.to_owned());
+        assert_eq!(mark(root.try_resolve_part(part.to_vec()).unwrap()), Some(1));
+        // the placeholder keeps its link
+        let placeholder = root.get_by_path("Mob/0100100.img/stand/1").unwrap();
+        assert!(placeholder.try_get(INLINK).unwrap().is_some());
+
+        assert!(root.resolve_canvas("Mob/0100100.img/gone").is_none());
+        assert!(root.resolve_canvas("Mob/0100100.img/none").is_none());
+        assert!(matches!(
+            root.try_resolve_canvas("Mob/0100100.img/loop"),
+            Err(Error::UnexpectedData(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,479 @@
+use crate::extra::{Entry, EntryPrimitive, EntryValue};
+use crate::{Canvas, EntryKind, Error, ImageKind, PixelLayout, Sound, UOL, Vector2D};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum Change {
+    /// path only in the new root, children are not listed.
+    Added { path: String, kind: String },
+    /// path only in the old root, children are not listed.
+    Removed { path: String, kind: String },
+    /// value at path differs, primitives are shown as is, canvases and
+    /// sounds as hashes.
+    Changed {
+        path: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl Change {
+    #[inline]
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DiffOptions {
+    /// canvases of the same size with mean channel difference in `0..=1` not
+    /// above this are the same.
+    pub canvas_threshold: Option<f64>,
+    /// descend into images even if directory size and checksum are equal.
+    pub deep: bool,
+}
+
+/// changes from an old root to a new root.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    /// walk both roots in order of name, added and removed subtrees are
+    /// reported at their top path only.
+    pub fn try_diff(old: &Entry, new: &Entry, opts: DiffOptions) -> Result<Diff, Error> {
+        let mut diff = Diff::default();
+        diff.walk(old, new, "", opts)?;
+        Ok(diff)
+    }
+
+    fn walk(
+        &mut self,
+        old: &Entry,
+        new: &Entry,
+        path: &str,
+        opts: DiffOptions,
+    ) -> Result<(), Error> {
+        // directory records carry size and checksum of images
+        if !opts.deep
+            && let (Some(os), Some(oc), Some(ns), Some(nc)) =
+                (old.size(), old.checksum(), new.size(), new.checksum())
+            && os == ns
+            && oc == nc
+        {
+            return Ok(());
+        }
+
+        let (old_canvas, new_canvas) = (canvas(old)?, canvas(new)?);
+        let old_value = value_text(old, old_canvas.as_ref())?;
+        let new_value = value_text(new, new_canvas.as_ref())?;
+        if old_value != new_value && !similar_canvas(old_canvas.as_ref(), new_canvas.as_ref(), opts)
+        {
+            self.changes.push(Change::Changed {
+                path: path.to_owned(),
+                old: old_value,
+                new: new_value,
+            });
+        }
+
+        if !old.has_children() && !new.has_children() {
+            return Ok(());
+        }
+        let mut children = BTreeMap::<String, (Option<Entry>, Option<Entry>)>::new();
+        if old.has_children() {
+            for e in old.try_iter()? {
+                let name = e.name().to_owned();
+                children.entry(name).or_default().0 = Some(e);
+            }
+        }
+        if new.has_children() {
+            for e in new.try_iter()? {
+                let name = e.name().to_owned();
+                children.entry(name).or_default().1 = Some(e);
+            }
+        }
+        for (name, pair) in children {
+            let child = if path.is_empty() {
+                name
+            } else {
+                format!("{path}/{name}")
+            };
+            match pair {
+                (Some(o), Some(n)) => self.walk(&o, &n, &child, opts)?,
+                (Some(o), None) => self.changes.push(Change::Removed {
+                    path: child,
+                    kind: kind_text(o.value()),
+                }),
+                (None, Some(n)) => self.changes.push(Change::Added {
+                    path: child,
+                    kind: kind_text(n.value()),
+                }),
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// one change a line, `+` added, `-` removed and `~` changed.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for change in self.changes.iter() {
+            let _ = match change {
+                Change::Added { path, kind } => writeln!(text, "+ {path} ({kind})"),
+                Change::Removed { path, kind } => writeln!(text, "- {path} ({kind})"),
+                Change::Changed { path, old, new } => writeln!(text, "~ {path}: {old} -> {new}"),
+            };
+        }
+        text
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Unexpected(Box::new(e)))
+    }
+
+    /// standalone page with a table of changes.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>diff</title>\n\
+             <style>\
+             body{font-family:monospace}table{border-collapse:collapse}\
+             td,th{border:1px solid #ccc;padding:2px 6px;text-align:left}\
+             .added{background:#e6ffed}.removed{background:#ffeef0}.changed{background:#fff5b1}\
+             </style>\n</head>\n<body>\n",
+        );
+        let _ = writeln!(html, "<p>{} changes</p>", self.changes.len());
+        html.push_str("<table>\n<tr><th></th><th>path</th><th>old</th><th>new</th></tr>\n");
+        for change in self.changes.iter() {
+            let (class, sign, old, new) = match change {
+                Change::Added { kind, .. } => ("added", "+", "", kind.as_str()),
+                Change::Removed { kind, .. } => ("removed", "-", kind.as_str(), ""),
+                Change::Changed { old, new, .. } => ("changed", "~", old.as_str(), new.as_str()),
+            };
+            let _ = writeln!(
+                html,
+                "<tr class=\"{class}\"><td>{sign}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(change.path()),
+                escape_html(old),
+                escape_html(new),
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn kind_text(value: &EntryValue) -> String {
+    match value {
+        EntryValue::Kind(kind) => kind.as_str().to_owned(),
+        EntryValue::Primitive(primitive) => primitive_text(primitive),
+    }
+}
+
+fn primitive_text(primitive: &EntryPrimitive) -> String {
+    match primitive {
+        EntryPrimitive::Nil => "nil".to_owned(),
+        EntryPrimitive::Int16(i) => i.to_string(),
+        EntryPrimitive::Int32(i) => i.to_string(),
+        EntryPrimitive::Int64(i) => i.to_string(),
+        EntryPrimitive::Float32(f) => f.to_string(),
+        EntryPrimitive::Float64(f) => f.to_string(),
+        EntryPrimitive::String(s) => format!("{s:?}"),
+    }
+}
+
+/// canvas with its pixels decoded once for both the text and the threshold.
+struct DecodedCanvas {
+    canvas: Canvas,
+    /// `None` for formats can't be decoded.
+    pixels: Option<Vec<u8>>,
+}
+
+#[inline]
+fn canvas(entry: &Entry) -> Result<Option<DecodedCanvas>, Error> {
+    match entry.value() {
+        EntryValue::Kind(EntryKind::Image(ImageKind::Canvas)) => {
+            let canvas = entry.try_to::<Canvas>()?;
+            let pixels = decode(&canvas);
+            Ok(Some(DecodedCanvas { canvas, pixels }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// comparable text of value, containers are compared by kind only.
+fn value_text(entry: &Entry, canvas: Option<&DecodedCanvas>) -> Result<String, Error> {
+    if let Some(canvas) = canvas {
+        return Ok(canvas_text(canvas));
+    }
+    Ok(match entry.value() {
+        EntryValue::Primitive(primitive) => primitive_text(primitive),
+        EntryValue::Kind(EntryKind::Image(ImageKind::Sound)) => {
+            let sound = entry.try_to::<Sound>()?;
+            format!(
+                "sound {} bytes #{:016x}",
+                sound.raw_data().len(),
+                hash(sound.raw_data())
+            )
+        }
+        EntryValue::Kind(EntryKind::Image(ImageKind::Vector2D)) => {
+            let v = entry.try_to::<Vector2D>()?;
+            format!("({}, {})", v.x, v.y)
+        }
+        EntryValue::Kind(EntryKind::Image(ImageKind::UOL)) => {
+            format!("uol {:?}", entry.try_to::<UOL>()?.path)
+        }
+        EntryValue::Kind(kind) => kind.as_str().to_owned(),
+    })
+}
+
+/// canvases are hashed by pixels, so other encodings of the same pixels are
+/// equal, and by stored data for formats can't be decoded.
+#[inline]
+fn canvas_text(canvas: &DecodedCanvas) -> String {
+    let DecodedCanvas { canvas, pixels } = canvas;
+    let size = canvas.attr.size;
+    format!(
+        "canvas {}x{} {} #{:016x}",
+        size.x,
+        size.y,
+        canvas.attr.format,
+        hash(pixels.as_deref().unwrap_or(canvas.data()))
+    )
+}
+
+/// with `canvas_threshold`, canvases of the same size but other pixels are
+/// compared by channel difference. canvases can't be decoded are not similar.
+fn similar_canvas(
+    old: Option<&DecodedCanvas>,
+    new: Option<&DecodedCanvas>,
+    opts: DiffOptions,
+) -> bool {
+    let (Some(threshold), Some(old), Some(new)) = (opts.canvas_threshold, old, new) else {
+        return false;
+    };
+    if old.canvas.attr.size != new.canvas.attr.size {
+        return false;
+    }
+    let (Some(old), Some(new)) = (&old.pixels, &new.pixels) else {
+        return false;
+    };
+    if old.len() != new.len() {
+        return false;
+    }
+    if old.is_empty() {
+        return true;
+    }
+    let total = old
+        .iter()
+        .zip(new.iter())
+        .map(|(a, b)| a.abs_diff(*b) as u64)
+        .sum::<u64>();
+    total as f64 / (old.len() as f64 * u8::MAX as f64) <= threshold
+}
+
+#[inline]
+fn decode(canvas: &Canvas) -> Option<Vec<u8>> {
+    let mut pixels = vec![0; canvas.decoded_size()];
+    canvas.decode_into(&mut pixels, PixelLayout::Rgba8).ok()?;
+    Some(pixels)
+}
+
+/// 64-bit FNV-1a, hashes in reports are the same across builds and machines.
+#[inline]
+fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CanvasFormat;
+    use crate::crypto::MapleVersion;
+    use crate::reader::PackLayout;
+    use crate::reader::pack::tests::{TestEntry, temp_dir, write_pack};
+
+    fn pack(dir: &std::path::Path, entries: &[TestEntry]) -> Entry {
+        std::fs::create_dir_all(dir).unwrap();
+        write_pack(&dir.join("Etc_000.ms"), 2, PackLayout::V2, &[], entries, 0);
+        Entry::from_packs(dir, MapleVersion::from(95)).unwrap()
+    }
+
+    #[test]
+    fn added_removed_changed() {
+        let dir = temp_dir("diff");
+        let old = pack(
+            &dir.join("old"),
+            &[
+                TestEntry {
+                    name: "Same.img",
+                    data: b"#Property\nname = a\n",
+                },
+                TestEntry {
+                    name: "Item/Changed.img",
+                    data: b"#Property\nprice = 10\nkeep = 1\ninfo = {\n gone = 1\n}\n",
+                },
+                TestEntry {
+                    name: "Removed.img",
+                    data: b"#Property\n",
+                },
+            ],
+        );
+        let new = pack(
+            &dir.join("new"),
+            &[
+                TestEntry {
+                    name: "Same.img",
+                    data: b"#Property\nname = a\n",
+                },
+                TestEntry {
+                    name: "Item/Changed.img",
+                    data: b"#Property\nprice = 12\nkeep = 1\ninfo = {\n}\nnew = x\n",
+                },
+                TestEntry {
+                    name: "Added.img",
+                    data: b"#Property\n",
+                },
+            ],
+        );
+
+        let diff = Diff::try_diff(&old, &new, DiffOptions::default()).unwrap();
+        assert_eq!(
+            diff.changes,
+            [
+                Change::Added {
+                    path: "Added.img".to_owned(),
+                    kind: "Property".to_owned(),
+                },
+                Change::Removed {
+                    path: "Item/Changed.img/info/gone".to_owned(),
+                    kind: "\"1\"".to_owned(),
+                },
+                Change::Added {
+                    path: "Item/Changed.img/new".to_owned(),
+                    kind: "\"x\"".to_owned(),
+                },
+                Change::Changed {
+                    path: "Item/Changed.img/price".to_owned(),
+                    old: "\"10\"".to_owned(),
+                    new: "\"12\"".to_owned(),
+                },
+                Change::Removed {
+                    path: "Removed.img".to_owned(),
+                    kind: "Property".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_text().lines().collect::<Vec<_>>(),
+            [
+                "+ Added.img (Property)",
+                "- Item/Changed.img/info/gone (\"1\")",
+                "+ Item/Changed.img/new (\"x\")",
+                "~ Item/Changed.img/price: \"10\" -> \"12\"",
+                "- Removed.img (Property)",
+            ]
+        );
+        assert!(diff.to_html().contains("<p>5 changes</p>"));
+        assert!(
+            Diff::try_diff(&old, &old, DiffOptions::default())
+                .unwrap()
+                .changes
+                .is_empty()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn decoded(size: i32, format: CanvasFormat, data: Vec<u8>) -> DecodedCanvas {
+        let canvas = Canvas::with_data(Vector2D { x: size, y: size }, format, data);
+        let pixels = decode(&canvas);
+        DecodedCanvas { canvas, pixels }
+    }
+
+    fn bgra(pixel: u8) -> DecodedCanvas {
+        decoded(2, CanvasFormat::BGRA8888, vec![pixel; 16])
+    }
+
+    #[test]
+    fn canvas_threshold() {
+        let (a, b) = (bgra(100), bgra(102));
+        assert_ne!(canvas_text(&a), canvas_text(&b));
+        assert!(!similar_canvas(Some(&a), Some(&b), DiffOptions::default()));
+
+        let opts = |threshold| DiffOptions {
+            canvas_threshold: Some(threshold),
+            deep: false,
+        };
+        assert!(similar_canvas(Some(&a), Some(&b), opts(0.01)));
+        assert!(!similar_canvas(Some(&a), Some(&b), opts(0.001)));
+        assert!(!similar_canvas(Some(&a), None, opts(1.0)));
+    }
+
+    #[test]
+    fn same_pixels_of_other_blocks() {
+        // opaque alpha, and both colors are the same, so every color index
+        // picks the same color.
+        let dxt5 = |color: u16, indices: u32| {
+            let mut block = vec![0xff, 0xff, 0, 0, 0, 0, 0, 0];
+            block.extend([color.to_le_bytes(), color.to_le_bytes()].concat());
+            block.extend(indices.to_le_bytes());
+            decoded(4, CanvasFormat::DXT5, block)
+        };
+        let (a, b) = (dxt5(0xf800, 0), dxt5(0xf800, 0xffff_ffff));
+        assert_ne!(a.canvas.data(), b.canvas.data());
+        assert_eq!(canvas_text(&a), canvas_text(&b));
+        assert!(canvas_text(&a).starts_with("canvas 4x4 DXT5 #"));
+        assert_ne!(canvas_text(&a), canvas_text(&dxt5(0x07e0, 0)));
+    }
+
+    #[test]
+    fn undecodable_canvas() {
+        let bc7 = |byte| decoded(4, CanvasFormat::BC7, vec![byte; 16]);
+        let (a, b) = (bc7(1), bc7(2));
+        assert!(a.pixels.is_none());
+        assert!(canvas_text(&a).starts_with("canvas 4x4 BC7 #"));
+        // stored data is hashed instead
+        assert_eq!(
+            canvas_text(&a),
+            format!("canvas 4x4 BC7 #{:016x}", hash(&[1; 16]))
+        );
+        assert_ne!(canvas_text(&a), canvas_text(&b));
+        let opts = DiffOptions {
+            canvas_threshold: Some(1.0),
+            deep: false,
+        };
+        assert!(!similar_canvas(Some(&a), Some(&b), opts));
+    }
+
+    #[test]
+    fn stable_hash() {
+        assert_eq!(hash(b""), 0xcbf29ce484222325);
+        assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
@@ -1,7 +1,9 @@
-use crate::crypto::{MapleCipher, MapleVersion};
+use crate::crypto::{MapleCipher, MapleTableNone, MapleVersion};
 use crate::extra::bundle::Bundle;
 use crate::extra::cache::EntryCache;
 use crate::extra::iter::ComponentIter;
+use crate::extra::pack::{PackBundle, PackChild, PackImage};
+use crate::reader::StringDecoder;
 use crate::{
     AccessorBuilder, AccessorOpt, Directories, Directory, EntryKind, Error, ImageKind,
     PlainPrimitive, PlainProperties, PlainProperty, Primitive, Properties, Property, PropertyKind,
@@ -9,6 +11,7 @@ use crate::{
 };
 use std::io::SeekFrom;
 use std::path::Path;
+use std::rc::Rc;
 
 enum EntryBuilder {
     Value,
@@ -16,6 +19,9 @@ enum EntryBuilder {
     Bundle(Bundle),
     PlainProperties(PlainProperties),
     Complex(Box<dyn AccessorBuilder>, Bundle),
+    Pack(Rc<PackBundle>, Vec<String>),
+    PackImage(PackImage),
+    Overlay(Vec<Entry>),
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +47,9 @@ pub struct Entry {
     value: EntryValue,
     builder: EntryBuilder,
     cipher: Box<dyn MapleCipher>,
+    layer: Option<usize>,
+    size: Option<usize>,
+    checksum: Option<i32>,
 }
 
 impl Entry {
@@ -66,9 +75,84 @@ impl Entry {
             },
             value: EntryValue::Kind(EntryKind::Folder),
             builder: EntryBuilder::Bundle(bundle),
+            layer: None,
+            size: None,
+            checksum: None,
         })
     }
 
+    /// load `ms` pack files, `path` can be a single pack file or a directory
+    /// contains pack files like `Packs`.
+    pub fn from_packs<P: AsRef<Path>>(path: P, version: MapleVersion) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .ok_or(error::io_err_invalid_input())?;
+        let bundle = PackBundle::from_path(path)?.ok_or(Error::InvalidArgument)?;
+        Ok(Entry {
+            name: file_name.to_owned(),
+            cipher: MapleTableNone.into_boxed(),
+            opt: AccessorOpt {
+                offset: 0,
+                ver_hash: version.hash(),
+                parent_offset: 0,
+            },
+            value: EntryValue::Kind(EntryKind::Folder),
+            builder: EntryBuilder::Pack(Rc::new(bundle), vec![]),
+            layer: None,
+            size: None,
+            checksum: None,
+        })
+    }
+
+    /// decoder of single-byte strings for entries loaded from this one, only
+    /// take effect on entries from `Entry::from_path`, `Entry::from_packs` and `Entry::overlay`
+    /// and their pack images.
+    pub fn with_string_decoder(mut self, decoder: StringDecoder) -> Self {
+        match &mut self.builder {
+            EntryBuilder::Bundle(bundle) => bundle.set_string_decoder(decoder),
+            // the bundle may be shared with other entries, only this one changes
+            EntryBuilder::Pack(bundle, _) => *bundle = Rc::new(bundle.with_string_decoder(decoder)),
+            EntryBuilder::PackImage(image) => image.set_string_decoder(decoder),
+            EntryBuilder::Overlay(layers) => {
+                let overlay = std::mem::take(layers);
+                *layers = overlay
+                    .into_iter()
+                    .map(|layer| layer.with_string_decoder(decoder))
+                    .collect();
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// stack `layers` into a single folder, ordered from the lowest priority to the highest.
+    ///
+    /// child exists in several layers resolved to the highest one, folders with
+    /// same name are merged recursively. use `Entry::layer` to find out which
+    /// layer a child came from.
+    pub fn overlay(name: &str, layers: Vec<Entry>) -> Self {
+        let layers = layers
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut layer)| {
+                layer.layer = Some(i);
+                layer
+            })
+            .collect::<Vec<_>>();
+        Entry {
+            name: name.to_owned(),
+            opt: AccessorOpt::default(),
+            value: EntryValue::Kind(EntryKind::Folder),
+            builder: EntryBuilder::Overlay(layers),
+            cipher: MapleTableNone.into_boxed(),
+            layer: None,
+            size: None,
+            checksum: None,
+        }
+    }
+
     #[inline]
     pub fn name(&self) -> &str {
         self.name.as_str()
@@ -79,6 +163,24 @@ impl Entry {
         &self.value
     }
 
+    /// index of the `Entry::overlay` layer this entry came from.
+    #[inline]
+    pub fn layer(&self) -> Option<usize> {
+        self.layer
+    }
+
+    /// byte size of the image data, `None` for primitives and virtual folders.
+    #[inline]
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    /// checksum stored in the directory entry, only entries listed in a `wz` directory have.
+    #[inline]
+    pub fn checksum(&self) -> Option<i32> {
+        self.checksum
+    }
+
     #[inline]
     pub fn has_children(&self) -> bool {
         match &self.value {
@@ -88,6 +190,7 @@ impl Entry {
                     ImageKind::Canvas | ImageKind::Video | ImageKind::Sound | ImageKind::RawData
                 ),
                 EntryKind::Folder | EntryKind::Property(_) => true,
+                EntryKind::Unknown => false,
             },
             EntryValue::Primitive(_) => false,
         }
@@ -95,9 +198,16 @@ impl Entry {
 
     #[inline]
     pub fn builder(&self) -> Option<&dyn AccessorBuilder> {
+        self.try_builder().ok()
+    }
+
+    /// accessor builder of image data, pack images are decrypted on first call
+    /// and their errors are returned as is.
+    pub fn try_builder(&self) -> Result<&dyn AccessorBuilder, Error> {
         match &self.builder {
-            EntryBuilder::File(f) => Some(f.as_ref()),
-            _ => None,
+            EntryBuilder::File(f) => Ok(f.as_ref()),
+            EntryBuilder::PackImage(image) => image.try_builder(),
+            _ => Err(Error::InvalidDataType),
         }
     }
 
@@ -166,64 +276,8 @@ impl Entry {
     /// `try_iter` don't cache result. each call will access IO. be careful.
     pub fn try_iter<'a>(&'a self) -> Result<Box<dyn Iterator<Item = Entry> + 'a>, Error> {
         let (builder, bundle) = match &self.builder {
-            EntryBuilder::File(builder) => match self.value {
-                EntryValue::Kind(kind) => match kind {
-                    EntryKind::Folder => (Some(builder), None),
-                    EntryKind::Image(img) => {
-                        return match img {
-                            ImageKind::Canvas | ImageKind::Video => {
-                                let mut accessor = builder.accessor();
-                                accessor.seek(SeekFrom::Start(self.opt.offset as u64));
-                                let Some(props) = Properties::builtin(self.opt, accessor.as_mut())?
-                                else {
-                                    return Ok(Box::new(std::iter::empty()));
-                                };
-                                Ok(Box::new(
-                                    props
-                                        .into_inner()
-                                        .into_iter()
-                                        .map(|p| property_to_entry(p, self, builder.as_ref())),
-                                ))
-                            }
-                            ImageKind::Sound | ImageKind::RawData => {
-                                let mut accessor = builder.accessor();
-                                accessor.seek(SeekFrom::Start(self.opt.offset as u64));
-                                let Some(props) =
-                                    Properties::optional(self.opt, accessor.as_mut())?
-                                else {
-                                    return Ok(Box::new(std::iter::empty()));
-                                };
-                                Ok(Box::new(
-                                    props
-                                        .into_inner()
-                                        .into_iter()
-                                        .map(|p| property_to_entry(p, self, builder.as_ref())),
-                                ))
-                            }
-                            _ => Ok(Box::new(std::iter::empty())),
-                        };
-                    }
-                    EntryKind::Property(props) => {
-                        return match props {
-                            PropertyKind::Plain => Ok(Box::new(
-                                PlainProperties::try_from_builder(self.opt, builder.as_ref())?
-                                    .into_inner()
-                                    .into_iter()
-                                    .map(|p| plain_property_to_entry(p, self)),
-                            )),
-                            PropertyKind::Encode => Ok(Box::new(
-                                Properties::try_from_builder(self.opt, builder.as_ref())?
-                                    .into_inner()
-                                    .into_iter()
-                                    .map(|p| property_to_entry(p, self, builder.as_ref())),
-                            )),
-                        };
-                    }
-                },
-                _ => {
-                    return Ok(Box::new(std::iter::empty()));
-                }
-            },
+            EntryBuilder::File(builder) => return self.try_iter_file(builder.as_ref()),
+            EntryBuilder::PackImage(image) => return self.try_iter_file(image.try_builder()?),
             EntryBuilder::PlainProperties(plain_props) => {
                 return Ok(Box::new(
                     plain_props
@@ -233,6 +287,17 @@ impl Entry {
                 ));
             }
             EntryBuilder::Value => return Ok(Box::new(std::iter::empty())),
+            EntryBuilder::Pack(bundle, prefix) => {
+                return Ok(Box::new(
+                    bundle
+                        .children(prefix)?
+                        .into_iter()
+                        .map(|c| pack_child_to_entry(c, self, bundle, prefix)),
+                ));
+            }
+            EntryBuilder::Overlay(layers) => {
+                return Ok(Box::new(overlay_entries(layers)?.into_iter()));
+            }
             EntryBuilder::Bundle(bundle) => match self.value {
                 EntryValue::Kind(EntryKind::Folder) => (None, Some(bundle)),
                 _ => {
@@ -275,6 +340,64 @@ impl Entry {
         Ok(Box::new(entries.into_iter()))
     }
 
+    fn try_iter_file<'a>(
+        &'a self,
+        builder: &'a dyn AccessorBuilder,
+    ) -> Result<Box<dyn Iterator<Item = Entry> + 'a>, Error> {
+        let EntryValue::Kind(kind) = self.value else {
+            return Ok(Box::new(std::iter::empty()));
+        };
+        match kind {
+            EntryKind::Unknown => Ok(Box::new(std::iter::empty())),
+            EntryKind::Folder => Ok(Box::new(
+                directories_to_entries(self, self.opt, builder, None)?.into_iter(),
+            )),
+            EntryKind::Image(img) => match img {
+                ImageKind::Canvas | ImageKind::Video => {
+                    let mut accessor = builder.accessor();
+                    accessor.seek(SeekFrom::Start(self.opt.offset as u64));
+                    let Some(props) = Properties::builtin(self.opt, accessor.as_mut())? else {
+                        return Ok(Box::new(std::iter::empty()));
+                    };
+                    Ok(Box::new(
+                        props
+                            .into_inner()
+                            .into_iter()
+                            .map(move |p| property_to_entry(p, self, builder)),
+                    ))
+                }
+                ImageKind::Sound | ImageKind::RawData => {
+                    let mut accessor = builder.accessor();
+                    accessor.seek(SeekFrom::Start(self.opt.offset as u64));
+                    let Some(props) = Properties::optional(self.opt, accessor.as_mut())? else {
+                        return Ok(Box::new(std::iter::empty()));
+                    };
+                    Ok(Box::new(
+                        props
+                            .into_inner()
+                            .into_iter()
+                            .map(move |p| property_to_entry(p, self, builder)),
+                    ))
+                }
+                _ => Ok(Box::new(std::iter::empty())),
+            },
+            EntryKind::Property(props) => match props {
+                PropertyKind::Plain => Ok(Box::new(
+                    PlainProperties::try_from_builder(self.opt, builder)?
+                        .into_inner()
+                        .into_iter()
+                        .map(|p| plain_property_to_entry(p, self)),
+                )),
+                PropertyKind::Encode => Ok(Box::new(
+                    Properties::try_from_builder(self.opt, builder)?
+                        .into_inner()
+                        .into_iter()
+                        .map(move |p| property_to_entry(p, self, builder)),
+                )),
+            },
+        }
+    }
+
     #[inline]
     pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = Entry> + 'a> {
         self.try_iter().unwrap_or_else(|e| panic!("iter: {e}"))
@@ -302,7 +425,10 @@ fn directories_to_entries(
         .into_inner()
         .into_iter()
         .map(|x| {
-            let sub = bundle.and_then(|bundle| bundle.load_by_name(x.name.as_str()));
+            let sub = match x.kind {
+                EntryKind::Folder => bundle.and_then(|bundle| bundle.load_by_name(x.name.as_str())),
+                _ => None,
+            };
             directory_to_entry(x, entry, builder, sub)
         })
         .collect::<Vec<_>>())
@@ -324,12 +450,101 @@ fn directory_to_entry(
             None => EntryBuilder::File(builder.clone_boxed()),
         },
         cipher: entry.cipher.clone_boxed(),
+        layer: entry.layer,
+        size: Some(dir.size),
+        checksum: Some(dir.checksum),
     }
 }
 
+#[inline]
+fn pack_child_to_entry(
+    child: PackChild,
+    entry: &Entry,
+    bundle: &Rc<PackBundle>,
+    prefix: &[String],
+) -> Entry {
+    match child {
+        PackChild::Folder(name) => {
+            let mut prefix = prefix.to_vec();
+            prefix.push(name.clone());
+            Entry {
+                name,
+                opt: entry.opt,
+                value: EntryValue::Kind(EntryKind::Folder),
+                builder: EntryBuilder::Pack(bundle.clone(), prefix),
+                cipher: entry.cipher.clone_boxed(),
+                layer: entry.layer,
+                size: None,
+                checksum: None,
+            }
+        }
+        PackChild::Image(name, image, builder) => Entry {
+            name,
+            opt: entry.opt.clone_with_parent(image.offset, 0),
+            value: EntryValue::Kind(image.kind),
+            builder: EntryBuilder::PackImage(builder),
+            cipher: entry.cipher.clone_boxed(),
+            layer: entry.layer,
+            size: Some(image.size),
+            checksum: None,
+        },
+    }
+}
+
+fn overlay_entries(layers: &[Entry]) -> Result<Vec<Entry>, Error> {
+    let mut index = ahash::HashMap::<String, usize>::default();
+    let mut stacks: Vec<Vec<Entry>> = Vec::new();
+    for layer in layers {
+        for e in layer.try_iter()? {
+            if let Some(i) = index.get(e.name()) {
+                stacks[*i].push(e);
+            } else {
+                index.insert(e.name().to_owned(), stacks.len());
+                stacks.push(vec![e]);
+            }
+        }
+    }
+
+    Ok(stacks
+        .into_iter()
+        .filter_map(|mut stack| {
+            let top = stack.pop()?;
+            if !matches!(top.value, EntryValue::Kind(EntryKind::Folder)) {
+                return Some(top);
+            }
+            // folders shadowed by a non-folder entry are dropped
+            let mut folders = stack
+                .into_iter()
+                .rev()
+                .take_while(|e| matches!(e.value, EntryValue::Kind(EntryKind::Folder)))
+                .collect::<Vec<_>>();
+            if folders.is_empty() {
+                return Some(top);
+            }
+            folders.reverse();
+            let name = top.name.clone();
+            let opt = top.opt;
+            let cipher = top.cipher.clone_boxed();
+            let layer = top.layer;
+            folders.push(top);
+            Some(Entry {
+                name,
+                opt,
+                value: EntryValue::Kind(EntryKind::Folder),
+                builder: EntryBuilder::Overlay(folders),
+                cipher,
+                layer,
+                size: None,
+                checksum: None,
+            })
+        })
+        .collect())
+}
+
 #[inline]
 fn property_to_entry(prop: Property, entry: &Entry, builder: &dyn AccessorBuilder) -> Entry {
     let mut offset = prop.offset;
+    let mut size = None;
     let value = match prop.value {
         Primitive::Nil => EntryValue::Primitive(EntryPrimitive::Nil),
         Primitive::Int16(i) => EntryValue::Primitive(EntryPrimitive::Int16(i)),
@@ -340,6 +555,7 @@ fn property_to_entry(prop: Property, entry: &Entry, builder: &dyn AccessorBuilde
         Primitive::String(s) => EntryValue::Primitive(EntryPrimitive::String(s)),
         Primitive::Image(img) => {
             offset = img.offset;
+            size = Some(img.size);
             EntryValue::Kind(img.kind)
         }
     };
@@ -349,6 +565,9 @@ fn property_to_entry(prop: Property, entry: &Entry, builder: &dyn AccessorBuilde
         value,
         builder: EntryBuilder::File(builder.clone_boxed()),
         cipher: entry.cipher.clone_boxed(),
+        layer: entry.layer,
+        size,
+        checksum: None,
     }
 }
 
@@ -361,6 +580,9 @@ fn plain_property_to_entry(prop: PlainProperty, entry: &Entry) -> Entry {
             value: EntryValue::Primitive(EntryPrimitive::String(val)),
             builder: EntryBuilder::Value,
             cipher: entry.cipher.clone_boxed(),
+            layer: entry.layer,
+            size: None,
+            checksum: None,
         },
         PlainPrimitive::Nested(nested) => Entry {
             name: prop.name,
@@ -368,6 +590,92 @@ fn plain_property_to_entry(prop: PlainProperty, entry: &Entry) -> Entry {
             value: EntryValue::Kind(EntryKind::Property(PropertyKind::Plain)),
             builder: EntryBuilder::PlainProperties(nested),
             cipher: entry.cipher.clone_boxed(),
+            layer: entry.layer,
+            size: None,
+            checksum: None,
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::PackLayout;
+    use crate::reader::pack::tests::{TestEntry, temp_dir, write_pack};
+
+    fn decoder_of(entry: &Entry) -> StringDecoder {
+        entry.builder().unwrap().accessor().string_decoder()
+    }
+
+    #[test]
+    fn string_decoder_of_shared_pack() {
+        let dir = temp_dir("entry-decoder");
+        let entries = [TestEntry {
+            name: "Etc.img",
+            data: b"#Property",
+        }];
+        write_pack(
+            &dir.join("Data_000.ms"),
+            2,
+            PackLayout::V2,
+            &[],
+            &entries,
+            0,
+        );
+        let lossy = StringDecoder {
+            lossy: true,
+            ..Default::default()
+        };
+
+        let root = Entry::from_packs(&dir, MapleVersion::from(95)).unwrap();
+        // children keep the bundle shared
+        let before = root.get_exact("Etc.img");
+        let root = root.with_string_decoder(lossy);
+        let after = root.get_exact("Etc.img");
+        assert_eq!(decoder_of(&before), StringDecoder::default());
+        assert_eq!(decoder_of(&after), lossy);
+
+        // opened image is built again
+        let image = before.with_string_decoder(lossy);
+        assert_eq!(decoder_of(&image), lossy);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn size_and_checksum() {
+        use crate::crypto::MapleTableNone;
+        use crate::reader::wizet::tests::{VERSION, write_wizet};
+
+        let dir = temp_dir("entry-size");
+        let data: &[u8] = b"#Property\nname = snail\n";
+        let images = [TestEntry {
+            name: "0100100.img",
+            data,
+        }];
+        write_wizet(&dir.join("Mob.wz"), &images);
+        write_pack(&dir.join("Mob_000.ms"), 2, PackLayout::V2, &[], &images, 0);
+
+        let version = MapleVersion::from(VERSION);
+        let wz = Entry::from_path(
+            dir.join("Mob.wz"),
+            MapleTableNone.into_boxed(),
+            version,
+            false,
+        )
+        .unwrap();
+        let image = wz.get_exact("0100100.img");
+        let checksum = data.iter().map(|b| *b as i32).sum::<i32>();
+        assert_eq!(image.size(), Some(data.len()));
+        assert_eq!(image.checksum(), Some(checksum));
+        let name = image.get_exact("name");
+        assert_eq!((name.size(), name.checksum()), (None, None));
+        assert_eq!((wz.size(), wz.checksum()), (None, None));
+
+        // pack entries have no checksum of their data
+        let pack = Entry::from_packs(&dir, version).unwrap();
+        let image = pack.get_exact("0100100.img");
+        assert_eq!(image.size(), Some(data.len()));
+        assert_eq!(image.checksum(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
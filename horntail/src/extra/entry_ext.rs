@@ -1,9 +1,11 @@
 use crate::extra::EntryCache;
 use crate::extra::entry::{Entry, EntryPrimitive, EntryValue};
+use crate::reader::Accessor;
 use crate::{
-    Canvas, CanvasAttribute, Convex2D, EntryKind, Error, ImageKind, RawData, Script, Sound,
-    SoundAttribute, TryFromBuilder, UOL, Vector2D,
+    Canvas, CanvasAttribute, CanvasRef, Convex2D, EntryKind, Error, ImageKind, RawData, Script,
+    Sound, SoundAttribute, SoundRef, TryFromBinary, TryFromBuilder, UOL, Vector2D,
 };
+use std::io::SeekFrom;
 
 macro_rules! impl_try_from_entry_cache {
     ($typ:tt) => {
@@ -113,7 +115,7 @@ macro_rules! impl_try_from_builder {
                 if !matches!(entry.value(), $pattern) {
                     return Err(Error::InvalidDataType);
                 }
-                let builder = entry.builder().ok_or(Error::InvalidDataType)?;
+                let builder = entry.try_builder()?;
                 Self::try_from_builder(entry.accessor_opt(), builder)
             }
         }
@@ -156,6 +158,46 @@ impl_try_from_builder!([
     (UOL, EntryValue::Kind(EntryKind::Image(ImageKind::UOL))),
 ]);
 
+macro_rules! impl_try_from_binary {
+    ([$(($typ:ident,$pattern:pat),)*]) => {
+        $(
+        /// borrows the data of mapped files, only decrypted data is copied.
+        impl<'a> TryFrom<&'a Entry> for $typ<'a> {
+            type Error = Error;
+
+            fn try_from(entry: &'a Entry) -> Result<Self, Self::Error> {
+                if !matches!(entry.value(), $pattern) {
+                    return Err(Error::InvalidDataType);
+                }
+                let opt = entry.accessor_opt();
+                let mut accessor = entry.try_builder()?.binary().ok_or(Error::InvalidDataType)?;
+                accessor.try_seek(SeekFrom::Start(opt.offset as u64))?;
+                Self::try_from_binary(opt, &mut accessor)
+            }
+        }
+
+        impl<'a> TryFrom<&'a EntryCache> for $typ<'a> {
+            type Error = Error;
+
+            fn try_from(entry: &'a EntryCache) -> Result<Self, Self::Error> {
+                Self::try_from(entry.as_ref())
+            }
+        }
+        )*
+    };
+}
+
+impl_try_from_binary!([
+    (
+        CanvasRef,
+        EntryValue::Kind(EntryKind::Image(ImageKind::Canvas))
+    ),
+    (
+        SoundRef,
+        EntryValue::Kind(EntryKind::Image(ImageKind::Sound))
+    ),
+]);
+
 impl<'a, T> TryFrom<&'a Entry> for Vec<T>
 where
     for<'b> T: TryFrom<&'b Entry>,
@@ -187,3 +229,114 @@ where
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{MapleCipher, MapleTableNone, MapleVersion};
+    use crate::reader::pack::tests::{TestEntry, temp_dir};
+    use crate::reader::wizet::tests::{TestValue, VERSION, encode_image, write_wizet};
+
+    /// whether `inner` lies in the data `entry` maps.
+    fn is_mapped(entry: &Entry, inner: &[u8]) -> bool {
+        let mut accessor = entry.try_builder().unwrap().binary().unwrap();
+        let len = accessor.len();
+        let outer = accessor.try_borrow(len).unwrap().as_ptr_range();
+        outer.start <= inner.as_ptr_range().start && inner.as_ptr_range().end <= outer.end
+    }
+
+    #[test]
+    fn borrow_from_mapped_file() {
+        let dir = temp_dir("entry-borrow");
+        let pixels = (0..4 * 2 * 2).collect::<Vec<u8>>();
+        let wave = b"RIFF wave".to_vec();
+        let image = encode_image(&TestValue::Props(vec![
+            (
+                "stand",
+                TestValue::Canvas {
+                    size: (2, 2),
+                    format: 2,
+                    data: pixels.clone(),
+                    props: vec![],
+                },
+            ),
+            ("die", TestValue::Sound(wave.clone())),
+        ]));
+        let path = dir.join("Mob.wz");
+        write_wizet(
+            &path,
+            &[TestEntry {
+                name: "0100100.img",
+                data: &image,
+            }],
+        );
+
+        let root = Entry::from_path(
+            &path,
+            MapleTableNone.into_boxed(),
+            MapleVersion::from(VERSION),
+            false,
+        )
+        .unwrap();
+        let stand = root.get_exact("0100100.img").get_exact("stand");
+        let die = root.get_exact("0100100.img").get_exact("die");
+        let canvas = CanvasRef::try_from(&stand).unwrap();
+        assert!(canvas.is_borrowed());
+        assert!(is_mapped(&stand, canvas.compressed()));
+        assert_eq!(canvas.into_canvas().unwrap().data(), pixels);
+
+        let sound = SoundRef::try_from(&die).unwrap();
+        assert!(is_mapped(&die, sound.raw_data()));
+        assert_eq!(sound.raw_data(), wave);
+
+        assert!(matches!(
+            CanvasRef::try_from(&die),
+            Err(Error::InvalidDataType)
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn encoded_values() {
+        let dir = temp_dir("entry-values");
+        let image = encode_image(&TestValue::Props(vec![
+            ("nil", TestValue::Nil),
+            ("int", TestValue::Int(1000)),
+            ("double", TestValue::Double(0.5)),
+            ("str", TestValue::Str("snail")),
+            (
+                "info",
+                TestValue::Props(vec![("origin", TestValue::Vector(-3, 200))]),
+            ),
+            ("link", TestValue::Uol("../info")),
+        ]));
+        let path = dir.join("Mob.wz");
+        write_wizet(
+            &path,
+            &[TestEntry {
+                name: "0100100.img",
+                data: &image,
+            }],
+        );
+
+        let root = Entry::from_path(
+            &path,
+            MapleTableNone.into_boxed(),
+            MapleVersion::from(VERSION),
+            false,
+        )
+        .unwrap();
+        let image = root.get_exact("0100100.img");
+        assert!(!bool::try_from(&image.get_exact("nil")).unwrap());
+        assert_eq!(i32::try_from(&image.get_exact("int")).unwrap(), 1000);
+        assert_eq!(f64::try_from(&image.get_exact("double")).unwrap(), 0.5);
+        assert_eq!(String::try_from(&image.get_exact("str")).unwrap(), "snail");
+        let origin = Vector2D::try_from(&image.get_exact("info").get_exact("origin")).unwrap();
+        assert_eq!((origin.x, origin.y), (-3, 200));
+        assert_eq!(
+            UOL::try_from(&image.get_exact("link")).unwrap().path,
+            "../info"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,528 @@
+use crate::Error;
+use crate::extra::{EntryCache, EntryValue};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const IMAGE_EXTENSION: &str = ".img";
+const INFO: &str = "info";
+const SET_ITEM_INFO: &str = "Etc/SetItemInfo.img";
+/// folders of `Character` are not equips, bodies and heads are images at
+/// the root.
+const NOT_EQUIPS: [&str; 3] = ["Afterimage", "Face", "Hair"];
+
+/// value of `name` converted to `T`, default when missing or of other types,
+/// since the same property changes type across versions.
+pub(crate) fn lenient<'a, T>(entry: &'a EntryCache, name: &str) -> Result<T, Error>
+where
+    T: TryFrom<&'a EntryCache, Error = Error> + Default,
+{
+    match entry.try_get_value::<T>(name) {
+        Ok(value) => Ok(value.unwrap_or_default()),
+        Err(Error::InvalidDataType | Error::UnexpectedData(_)) => Ok(T::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// numeric children of `entry`, others are left out.
+pub(crate) fn numeric_fields(entry: &EntryCache) -> Result<BTreeMap<String, i64>, Error> {
+    let mut fields = BTreeMap::new();
+    for e in entry.try_iter()? {
+        if let EntryValue::Primitive(_) = e.value()
+            && let Ok(v) = e.try_to::<i64>()
+        {
+            fields.insert(e.name().to_owned(), v);
+        }
+    }
+    Ok(fields)
+}
+
+#[inline]
+fn parse_id(name: &str) -> Option<u32> {
+    name.trim_end_matches(IMAGE_EXTENSION).parse().ok()
+}
+
+/// `info` of `Item/<category>/<prefix>.img/<id>` and `Item/Pet/<id>.img`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ItemInfo {
+    pub id: u32,
+    /// folder in `Item`, e.g. `Consume`, `Etc`, `Pet`.
+    pub category: String,
+    pub price: i64,
+    pub slot_max: u32,
+    pub req_level: u32,
+    pub cash: bool,
+    pub trade_block: bool,
+    pub quest: bool,
+    pub only: bool,
+    /// effects of consumables.
+    pub spec: BTreeMap<String, i64>,
+}
+
+impl ItemInfo {
+    pub fn try_from_entry(id: u32, category: &str, item: &EntryCache) -> Result<Self, Error> {
+        let mut info = ItemInfo {
+            id,
+            category: category.to_owned(),
+            ..Default::default()
+        };
+        if let Some(e) = item.try_get(INFO)? {
+            info.price = lenient(e, "price")?;
+            info.slot_max = lenient(e, "slotMax")?;
+            info.req_level = lenient(e, "reqLevel")?;
+            info.cash = lenient(e, "cash")?;
+            info.trade_block = lenient(e, "tradeBlock")?;
+            info.quest = lenient(e, "quest")?;
+            info.only = lenient(e, "only")?;
+        }
+        if let Some(spec) = item.try_get("spec")? {
+            info.spec = numeric_fields(spec)?;
+        }
+        Ok(info)
+    }
+}
+
+/// `info` of `Character/<category>/<id>.img`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct EquipInfo {
+    pub id: u32,
+    /// folder in `Character`, e.g. `Cap`, `Weapon`.
+    pub category: String,
+    pub price: i64,
+    pub req_level: u32,
+    pub req_job: i32,
+    pub req_str: u32,
+    pub req_dex: u32,
+    pub req_int: u32,
+    pub req_luk: u32,
+    pub inc_str: i32,
+    pub inc_dex: i32,
+    pub inc_int: i32,
+    pub inc_luk: i32,
+    pub inc_mhp: i32,
+    pub inc_mmp: i32,
+    pub inc_pad: i32,
+    pub inc_mad: i32,
+    pub inc_pdd: i32,
+    pub inc_mdd: i32,
+    pub inc_acc: i32,
+    pub inc_eva: i32,
+    pub inc_speed: i32,
+    pub inc_jump: i32,
+    /// upgrade slots.
+    pub tuc: u32,
+    pub cash: bool,
+    pub trade_block: bool,
+    pub only: bool,
+    /// `setItemID`, or the set of the lowest id listing this equip.
+    pub set_item: Option<u32>,
+}
+
+impl EquipInfo {
+    pub fn try_from_entry(id: u32, category: &str, equip: &EntryCache) -> Result<Self, Error> {
+        let mut info = EquipInfo {
+            id,
+            category: category.to_owned(),
+            ..Default::default()
+        };
+        let Some(e) = equip.try_get(INFO)? else {
+            return Ok(info);
+        };
+        info.price = lenient(e, "price")?;
+        info.req_level = lenient(e, "reqLevel")?;
+        info.req_job = lenient(e, "reqJob")?;
+        info.req_str = lenient(e, "reqSTR")?;
+        info.req_dex = lenient(e, "reqDEX")?;
+        info.req_int = lenient(e, "reqINT")?;
+        info.req_luk = lenient(e, "reqLUK")?;
+        info.inc_str = lenient(e, "incSTR")?;
+        info.inc_dex = lenient(e, "incDEX")?;
+        info.inc_int = lenient(e, "incINT")?;
+        info.inc_luk = lenient(e, "incLUK")?;
+        info.inc_mhp = lenient(e, "incMHP")?;
+        info.inc_mmp = lenient(e, "incMMP")?;
+        info.inc_pad = lenient(e, "incPAD")?;
+        info.inc_mad = lenient(e, "incMAD")?;
+        info.inc_pdd = lenient(e, "incPDD")?;
+        info.inc_mdd = lenient(e, "incMDD")?;
+        info.inc_acc = lenient(e, "incACC")?;
+        info.inc_eva = lenient(e, "incEVA")?;
+        info.inc_speed = lenient(e, "incSpeed")?;
+        info.inc_jump = lenient(e, "incJump")?;
+        info.tuc = lenient(e, "tuc")?;
+        info.cash = lenient(e, "cash")?;
+        info.trade_block = lenient(e, "tradeBlock")?;
+        info.only = lenient(e, "only")?;
+        info.set_item = e
+            .try_get("setItemID")?
+            .and_then(|e| e.try_to::<u32>().ok())
+            .filter(|id| *id != 0);
+        Ok(info)
+    }
+}
+
+/// `Etc/SetItemInfo.img/<id>`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct SetItem {
+    pub id: u32,
+    pub name: String,
+    pub complete_count: u32,
+    pub items: Vec<u32>,
+    /// stats by the count of equipped items.
+    pub effects: BTreeMap<u32, BTreeMap<String, i64>>,
+}
+
+impl SetItem {
+    pub fn try_from_entry(id: u32, set: &EntryCache) -> Result<Self, Error> {
+        let mut items = vec![];
+        if let Some(ids) = set.try_get("ItemID")? {
+            for e in ids.try_iter()? {
+                // newer versions group alternatives of a slot
+                if e.has_children() {
+                    for e in e.try_iter()? {
+                        items.extend(e.try_to::<u32>().ok());
+                    }
+                } else {
+                    items.extend(e.try_to::<u32>().ok());
+                }
+            }
+        }
+        items.sort_unstable();
+        items.dedup();
+
+        let mut effects = BTreeMap::new();
+        if let Some(effect) = set.try_get("Effect")? {
+            for e in effect.try_iter()? {
+                if let Some(count) = parse_id(e.name()) {
+                    effects.insert(count, numeric_fields(e)?);
+                }
+            }
+        }
+        Ok(SetItem {
+            id,
+            name: lenient(set, "setItemName")?,
+            complete_count: lenient(set, "completeCount")?,
+            items,
+            effects,
+        })
+    }
+}
+
+/// every item of `Item`, equip of `Character` and set of
+/// `Etc/SetItemInfo.img` in order of id.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ItemTable {
+    pub items: Vec<ItemInfo>,
+    pub equips: Vec<EquipInfo>,
+    pub sets: Vec<SetItem>,
+}
+
+impl ItemTable {
+    /// `root` holds `Item`, `Character` and `Etc`, missing ones are skipped.
+    pub fn try_load(root: &EntryCache) -> Result<ItemTable, Error> {
+        let mut table = ItemTable::default();
+        if let Some(item) = root.try_get("Item")? {
+            for category in item.try_iter()? {
+                for image in category.try_iter()? {
+                    // pets are one image per item
+                    if let Some(id) = parse_id(image.name())
+                        && image.try_get(INFO)?.is_some()
+                    {
+                        let info = ItemInfo::try_from_entry(id, category.name(), image)?;
+                        table.items.push(info);
+                        continue;
+                    }
+                    for e in image.try_iter()? {
+                        if let Some(id) = parse_id(e.name()) {
+                            let info = ItemInfo::try_from_entry(id, category.name(), e)?;
+                            table.items.push(info);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(character) = root.try_get("Character")? {
+            for category in character.try_iter()? {
+                if NOT_EQUIPS.contains(&category.name())
+                    || category.name().ends_with(IMAGE_EXTENSION)
+                {
+                    continue;
+                }
+                for image in category.try_iter()? {
+                    if let Some(id) = parse_id(image.name()) {
+                        let info = EquipInfo::try_from_entry(id, category.name(), image)?;
+                        table.equips.push(info);
+                    }
+                }
+            }
+        }
+
+        if let Some(sets) = root.try_get_by_path(SET_ITEM_INFO)? {
+            for e in sets.try_iter()? {
+                if let Some(id) = parse_id(e.name()) {
+                    table.sets.push(SetItem::try_from_entry(id, e)?);
+                }
+            }
+        }
+
+        table.items.sort_by_key(|i| i.id);
+        table.equips.sort_by_key(|e| e.id);
+        table.sets.sort_by_key(|s| s.id);
+
+        // `setItemID` of the equip wins, otherwise the set of the lowest id
+        // listing it, since sets are in order of id.
+        let mut members = ahash::HashMap::default();
+        for set in table.sets.iter() {
+            for item in set.items.iter() {
+                members.entry(*item).or_insert(set.id);
+            }
+        }
+        for equip in table.equips.iter_mut() {
+            if equip.set_item.is_none() {
+                equip.set_item = members.get(&equip.id).copied();
+            }
+        }
+        Ok(table)
+    }
+
+    /// items as CSV with a header line, `spec` is written as `key=value;...`.
+    pub fn items_csv(&self) -> String {
+        let mut csv =
+            String::from("id,category,price,slot_max,req_level,cash,trade_block,quest,only,spec\n");
+        for i in self.items.iter() {
+            let spec = i
+                .spec
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join(";");
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{}",
+                i.id,
+                csv_field(&i.category),
+                i.price,
+                i.slot_max,
+                i.req_level,
+                i.cash,
+                i.trade_block,
+                i.quest,
+                i.only,
+                csv_field(&spec),
+            );
+        }
+        csv
+    }
+
+    /// equips as CSV with a header line.
+    pub fn equips_csv(&self) -> String {
+        let mut csv = String::from(
+            "id,category,price,req_level,req_job,req_str,req_dex,req_int,req_luk,\
+             inc_str,inc_dex,inc_int,inc_luk,inc_mhp,inc_mmp,inc_pad,inc_mad,inc_pdd,inc_mdd,\
+             inc_acc,inc_eva,inc_speed,inc_jump,tuc,cash,trade_block,only,set_item\n",
+        );
+        for e in self.equips.iter() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                e.id,
+                csv_field(&e.category),
+                e.price,
+                e.req_level,
+                e.req_job,
+                e.req_str,
+                e.req_dex,
+                e.req_int,
+                e.req_luk,
+                e.inc_str,
+                e.inc_dex,
+                e.inc_int,
+                e.inc_luk,
+                e.inc_mhp,
+                e.inc_mmp,
+                e.inc_pad,
+                e.inc_mad,
+                e.inc_pdd,
+                e.inc_mdd,
+                e.inc_acc,
+                e.inc_eva,
+                e.inc_speed,
+                e.inc_jump,
+                e.tuc,
+                e.cash,
+                e.trade_block,
+                e.only,
+                e.set_item.map(|s| s.to_string()).unwrap_or_default(),
+            );
+        }
+        csv
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Unexpected(Box::new(e)))
+    }
+}
+
+/// quote fields contain separators.
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::pack::tests::encoded_root;
+    use crate::reader::wizet::tests::TestValue::{self, Int, Props, Str};
+
+    fn info(props: Vec<(&'static str, TestValue)>) -> TestValue {
+        Props(vec![(INFO, Props(props))])
+    }
+
+    fn columns(csv: &str) -> Vec<usize> {
+        // fields here are quoted only for the separators in them
+        csv.lines()
+            .map(|line| {
+                let mut quoted = false;
+                1 + line
+                    .chars()
+                    .filter(|c| {
+                        quoted ^= *c == '"';
+                        *c == ',' && !quoted
+                    })
+                    .count()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn load_table() {
+        let (dir, root) = encoded_root(
+            "item-table",
+            &[
+                (
+                    "Item/Consume/0200.img",
+                    Props(vec![
+                        (
+                            "02000000",
+                            Props(vec![
+                                (INFO, Props(vec![("price", Int(50)), ("slotMax", Int(100))])),
+                                ("spec", Props(vec![("hp", Int(50)), ("script", Str("x"))])),
+                            ]),
+                        ),
+                        // types of other versions are left as default
+                        ("02000001", info(vec![("price", Str("cheap"))])),
+                    ]),
+                ),
+                ("Item/Pet/5000000.img", info(vec![("cash", Int(1))])),
+                (
+                    "Character/Cap/01002000.img",
+                    info(vec![
+                        ("reqLevel", Int(10)),
+                        ("incSTR", Int(2)),
+                        ("setItemID", Int(7)),
+                    ]),
+                ),
+                ("Character/Cap/01002001.img", info(vec![("tuc", Int(7))])),
+                ("Character/Cap/01002002.img", info(vec![])),
+                ("Character/Face/00020000.img", info(vec![])),
+                ("Character/00002000.img", info(vec![])),
+                (
+                    "Etc/SetItemInfo.img",
+                    Props(vec![
+                        (
+                            "2",
+                            Props(vec![
+                                ("setItemName", Str("second")),
+                                (
+                                    "ItemID",
+                                    Props(vec![("1", Int(1002001)), ("2", Int(1002002))]),
+                                ),
+                            ]),
+                        ),
+                        (
+                            "1",
+                            Props(vec![
+                                ("setItemName", Str("first, \"a\"")),
+                                ("completeCount", Int(2)),
+                                (
+                                    "ItemID",
+                                    Props(vec![(
+                                        "1",
+                                        Props(vec![("0", Int(1002001)), ("1", Int(1002000))]),
+                                    )]),
+                                ),
+                                (
+                                    "Effect",
+                                    Props(vec![("2", Props(vec![("incSTR", Int(5))]))]),
+                                ),
+                            ]),
+                        ),
+                    ]),
+                ),
+            ],
+        );
+        let table = ItemTable::try_load(&root).unwrap();
+
+        let ids = table.items.iter().map(|i| i.id).collect::<Vec<_>>();
+        assert_eq!(ids, [2000000, 2000001, 5000000]);
+        let potion = &table.items[0];
+        assert_eq!((potion.category.as_str(), potion.price), ("Consume", 50));
+        assert_eq!(potion.slot_max, 100);
+        assert_eq!(potion.spec, BTreeMap::from([("hp".to_owned(), 50)]));
+        assert_eq!(table.items[1].price, 0);
+        assert!(table.items[2].cash);
+        assert_eq!(table.items[2].category, "Pet");
+
+        let ids = table.equips.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids, [1002000, 1002001, 1002002]);
+        assert_eq!(
+            (table.equips[0].req_level, table.equips[0].inc_str),
+            (10, 2)
+        );
+        // own `setItemID` wins over the sets listing the equip
+        assert_eq!(table.equips[0].set_item, Some(7));
+        // the lowest set wins when sets share an equip
+        assert_eq!(table.equips[1].set_item, Some(1));
+        assert_eq!(table.equips[2].set_item, Some(2));
+
+        let set = &table.sets[0];
+        assert_eq!((set.id, set.complete_count), (1, 2));
+        assert_eq!(set.items, [1002000, 1002001]);
+        assert_eq!(set.effects[&2], BTreeMap::from([("incSTR".to_owned(), 5)]));
+        assert_eq!(table.sets[1].items, [1002001, 1002002]);
+
+        let csv = table.items_csv();
+        assert_eq!(columns(&csv), [10; 4]);
+        assert!(csv.contains("\n2000000,Consume,50,100,0,false,false,false,false,hp=50\n"));
+        let csv = table.equips_csv();
+        assert_eq!(columns(&csv), [28; 4]);
+        assert!(
+            csv.lines()
+                .nth(2)
+                .unwrap()
+                .ends_with(",7,false,false,false,1")
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn quote_csv_fields() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+}
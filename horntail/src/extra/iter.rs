@@ -27,3 +27,19 @@ impl<'a> Iterator for ComponentIter<'a> {
         }
     }
 }
+
+/// join `UOL` style `path` which relative to `base`, `None` when it goes
+/// beyond the root.
+pub(crate) fn join_relative(base: &[String], path: &str) -> Option<Vec<String>> {
+    let mut components = base.to_vec();
+    for c in path.split('/') {
+        match c {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            c => components.push(c.to_owned()),
+        }
+    }
+    Some(components)
+}
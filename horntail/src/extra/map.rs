@@ -0,0 +1,674 @@
+use crate::extra::EntryCache;
+use crate::{Canvas, Error, Vector2D};
+#[cfg(feature = "image")]
+use image::{Rgba, RgbaImage, imageops};
+
+const MAP: &str = "Map";
+const LAYERS: i32 = 8;
+const OPAQUE: u8 = u8::MAX;
+
+/// a canvas placed in map coordinates.
+pub struct MapSprite {
+    pub canvas: Canvas,
+    /// left top of canvas.
+    pub position: Vector2D,
+    /// mirrored horizontally.
+    pub flip: bool,
+    pub alpha: u8,
+    /// repeat interval of tiled backgrounds, `0` for no repeat on the axis.
+    pub tile: Vector2D,
+}
+
+impl MapSprite {
+    fn new(canvas: &EntryCache, x: i32, y: i32, flip: bool) -> Result<MapSprite, Error> {
+        let origin = canvas.try_get_default::<Vector2D>("origin")?;
+        let canvas = canvas.try_to::<Canvas>()?;
+        let origin_x = if flip {
+            canvas.attr.size.x - origin.x
+        } else {
+            origin.x
+        };
+        Ok(MapSprite {
+            position: Vector2D {
+                x: x - origin_x,
+                y: y - origin.y,
+            },
+            canvas,
+            flip,
+            alpha: OPAQUE,
+            tile: Vector2D::default(),
+        })
+    }
+
+    #[inline]
+    fn right_bottom(&self) -> Vector2D {
+        Vector2D {
+            x: self.position.x + self.canvas.attr.size.x,
+            y: self.position.y + self.canvas.attr.size.y,
+        }
+    }
+}
+
+/// `type` of `back`, scrolling is ignored for static rendering.
+#[inline]
+fn back_tiling(typ: i32) -> (bool, bool) {
+    match typ {
+        1 | 4 => (true, false),
+        2 | 5 => (false, true),
+        3 | 6 | 7 => (true, true),
+        _ => (false, false),
+    }
+}
+
+/// overlays drawn over the map.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct MapOverlay {
+    pub footholds: bool,
+    pub portals: bool,
+    pub ladders: bool,
+    pub life: bool,
+}
+
+/// render maps of `Map/Map/MapN/<id>.img` with `Map/Back`, `Map/Tile` and
+/// `Map/Obj`.
+pub struct MapRenderer<'a> {
+    root: &'a EntryCache,
+}
+
+impl<'a> MapRenderer<'a> {
+    /// `root` is the bundle root holds `Map`.
+    #[inline]
+    pub fn new(root: &'a EntryCache) -> Self {
+        MapRenderer { root }
+    }
+
+    fn try_map(&self, id: u32) -> Result<&'a EntryCache, Error> {
+        self.root
+            .try_get_by_path(map_path(id))?
+            .ok_or(Error::InvalidArgument)
+    }
+
+    fn sprite(&self, path: String) -> Result<Option<&'a EntryCache>, Error> {
+        self.root
+            .try_resolve_part(path.split('/').map(|c| c.to_owned()).collect())
+    }
+
+    /// sprites from the back to the front, backgrounds parallax is ignored
+    /// as if the camera is at the map origin.
+    pub fn sprites(&self, id: u32) -> Result<Vec<MapSprite>, Error> {
+        let map = self.try_map(id)?;
+        let (mut backs, mut fronts) = (vec![], vec![]);
+        if let Some(back) = map.try_get("back")? {
+            for (_, e) in numbered(back)? {
+                let set = e.try_get_default::<String>("bS")?;
+                if set.is_empty() {
+                    continue;
+                }
+                let no = e.try_get_default::<String>("no")?;
+                let path = match e.try_get_default::<i32>("ani")? {
+                    0 => format!("{MAP}/Back/{set}.img/back/{no}"),
+                    1 => format!("{MAP}/Back/{set}.img/ani/{no}/0"),
+                    // spine
+                    _ => continue,
+                };
+                let Some(canvas) = self.sprite(path)? else {
+                    continue;
+                };
+                let mut sprite = MapSprite::new(
+                    canvas,
+                    e.try_get_default("x")?,
+                    e.try_get_default("y")?,
+                    e.try_get_default("f")?,
+                )?;
+                sprite.alpha = e.try_get_or_else("a", || Ok(OPAQUE as i32))?.clamp(0, 255) as u8;
+                let (htile, vtile) = back_tiling(e.try_get_default("type")?);
+                if htile {
+                    sprite.tile.x = match e.try_get_default::<i32>("cx")? {
+                        0 => sprite.canvas.attr.size.x,
+                        cx => cx,
+                    };
+                }
+                if vtile {
+                    sprite.tile.y = match e.try_get_default::<i32>("cy")? {
+                        0 => sprite.canvas.attr.size.y,
+                        cy => cy,
+                    };
+                }
+                if e.try_get_default::<bool>("front")? {
+                    fronts.push(sprite);
+                } else {
+                    backs.push(sprite);
+                }
+            }
+        }
+
+        // objects go before tiles in a layer
+        let mut layered = vec![];
+        for layer in 0..LAYERS {
+            let Some(entry) = map.try_get(&layer.to_string())? else {
+                continue;
+            };
+            if let Some(obj) = entry.try_get("obj")? {
+                for (i, e) in numbered(obj)? {
+                    let path = format!(
+                        "{MAP}/Obj/{}.img/{}/{}/{}/0",
+                        e.try_get_default::<String>("oS")?,
+                        e.try_get_default::<String>("l0")?,
+                        e.try_get_default::<String>("l1")?,
+                        e.try_get_default::<String>("l2")?,
+                    );
+                    let Some(canvas) = self.sprite(path)? else {
+                        continue;
+                    };
+                    let key = (
+                        layer,
+                        0,
+                        e.try_get_default::<i32>("z")?,
+                        e.try_get_default::<i32>("zM")?,
+                        i,
+                    );
+                    let sprite = MapSprite::new(
+                        canvas,
+                        e.try_get_default("x")?,
+                        e.try_get_default("y")?,
+                        e.try_get_default("f")?,
+                    )?;
+                    layered.push((key, sprite));
+                }
+            }
+            let set = match entry.try_get_by_path("info/tS")? {
+                Some(e) => e.try_to::<String>()?,
+                None => continue,
+            };
+            if let Some(tile) = entry.try_get("tile")? {
+                for (i, e) in numbered(tile)? {
+                    let path = format!(
+                        "{MAP}/Tile/{set}.img/{}/{}",
+                        e.try_get_default::<String>("u")?,
+                        e.try_get_default::<String>("no")?,
+                    );
+                    let Some(canvas) = self.sprite(path)? else {
+                        continue;
+                    };
+                    let key = (
+                        layer,
+                        1,
+                        canvas.try_get_default::<i32>("z")?,
+                        e.try_get_default::<i32>("zM")?,
+                        i,
+                    );
+                    let sprite = MapSprite::new(
+                        canvas,
+                        e.try_get_default("x")?,
+                        e.try_get_default("y")?,
+                        false,
+                    )?;
+                    layered.push((key, sprite));
+                }
+            }
+        }
+        layered.sort_by_key(|(key, _)| *key);
+
+        backs.extend(layered.into_iter().map(|(_, sprite)| sprite));
+        backs.extend(fronts);
+        Ok(backs)
+    }
+
+    /// left top and right bottom of the map, from `info/VR*` or covers all
+    /// footholds and untiled sprites.
+    pub fn bounds(&self, id: u32, sprites: &[MapSprite]) -> Result<(Vector2D, Vector2D), Error> {
+        let map = self.try_map(id)?;
+        if let Some(info) = map.try_get("info")?
+            && let (Some(l), Some(t), Some(r), Some(b)) = (
+                info.try_get_value::<i32>("VRLeft")?,
+                info.try_get_value::<i32>("VRTop")?,
+                info.try_get_value::<i32>("VRRight")?,
+                info.try_get_value::<i32>("VRBottom")?,
+            )
+            && l < r
+            && t < b
+        {
+            return Ok((Vector2D { x: l, y: t }, Vector2D { x: r, y: b }));
+        }
+
+        let mut rects = sprites
+            .iter()
+            .filter(|s| s.tile == Vector2D::default())
+            .map(|s| (s.position, s.right_bottom()))
+            .collect::<Vec<_>>();
+        rects.extend(self.footholds(map)?);
+        let mut iter = rects.into_iter().map(|(a, b)| {
+            (
+                Vector2D {
+                    x: a.x.min(b.x),
+                    y: a.y.min(b.y),
+                },
+                Vector2D {
+                    x: a.x.max(b.x),
+                    y: a.y.max(b.y),
+                },
+            )
+        });
+        let first = iter.next().ok_or(Error::InvalidArgument)?;
+        Ok(iter.fold(first, |(lt, rb), (a, b)| {
+            (
+                Vector2D {
+                    x: lt.x.min(a.x),
+                    y: lt.y.min(a.y),
+                },
+                Vector2D {
+                    x: rb.x.max(b.x),
+                    y: rb.y.max(b.y),
+                },
+            )
+        }))
+    }
+
+    /// segments of `foothold/<layer>/<group>/<id>`.
+    fn footholds(&self, map: &EntryCache) -> Result<Vec<(Vector2D, Vector2D)>, Error> {
+        let mut segments = vec![];
+        let Some(foothold) = map.try_get("foothold")? else {
+            return Ok(segments);
+        };
+        for layer in foothold.try_iter()? {
+            for group in layer.try_iter()? {
+                for e in group.try_iter()? {
+                    segments.push((
+                        Vector2D {
+                            x: e.try_get_default("x1")?,
+                            y: e.try_get_default("y1")?,
+                        },
+                        Vector2D {
+                            x: e.try_get_default("x2")?,
+                            y: e.try_get_default("y2")?,
+                        },
+                    ));
+                }
+            }
+        }
+        Ok(segments)
+    }
+}
+
+/// path of map image from the bundle root.
+#[inline]
+pub(crate) fn map_path(id: u32) -> String {
+    format!("{MAP}/Map/Map{}/{id:09}.img", id / 100000000)
+}
+
+/// children named by numbers, in order.
+pub(crate) fn numbered(entry: &EntryCache) -> Result<Vec<(usize, &EntryCache)>, Error> {
+    let mut children = entry
+        .try_iter()?
+        .filter_map(|e| e.name().parse::<usize>().ok().map(|i| (i, e)))
+        .collect::<Vec<_>>();
+    children.sort_by_key(|(i, _)| *i);
+    Ok(children)
+}
+
+#[cfg(feature = "image")]
+const FOOTHOLD_COLOR: Rgba<u8> = Rgba([0, 255, 0, 255]);
+#[cfg(feature = "image")]
+const LADDER_COLOR: Rgba<u8> = Rgba([255, 255, 0, 255]);
+#[cfg(feature = "image")]
+const PORTAL_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+#[cfg(feature = "image")]
+const MOB_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+#[cfg(feature = "image")]
+const NPC_COLOR: Rgba<u8> = Rgba([0, 128, 255, 255]);
+#[cfg(feature = "image")]
+const MARKER_SIZE: i32 = 3;
+/// pixels of the largest map rendered, 256 MiB in RGBA.
+#[cfg(feature = "image")]
+const MAX_RENDER_PIXELS: i64 = 1 << 26;
+
+#[cfg(feature = "image")]
+impl MapRenderer<'_> {
+    /// the whole map in one image, its left top is the left top of `bounds`.
+    /// `Error::InvalidArgument` when the bounds are empty or too large.
+    pub fn render(&self, id: u32, overlay: MapOverlay) -> Result<RgbaImage, Error> {
+        let sprites = self.sprites(id)?;
+        let (lt, rb) = self.bounds(id, &sprites)?;
+        let (width, height) = (rb.x as i64 - lt.x as i64, rb.y as i64 - lt.y as i64);
+        if width <= 0 || height <= 0 || width * height > MAX_RENDER_PIXELS {
+            return Err(Error::InvalidArgument);
+        }
+        let mut image = RgbaImage::new(width as u32, height as u32);
+
+        for sprite in sprites.iter() {
+            let Some(mut part) = sprite.canvas.image() else {
+                continue;
+            };
+            if sprite.flip {
+                imageops::flip_horizontal_in_place(&mut part);
+            }
+            if sprite.alpha != OPAQUE {
+                part.pixels_mut()
+                    .for_each(|p| p[3] = (p[3] as u32 * sprite.alpha as u32 / 255) as u8);
+            }
+            let ys = repeat(sprite.position.y, sprite.tile.y, lt.y, rb.y);
+            for x in repeat(sprite.position.x, sprite.tile.x, lt.x, rb.x) {
+                for y in ys.iter().copied() {
+                    imageops::overlay(&mut image, &part, (x - lt.x) as i64, (y - lt.y) as i64);
+                }
+            }
+        }
+
+        let map = self.try_map(id)?;
+        if overlay.footholds {
+            for (a, b) in self.footholds(map)? {
+                draw_line(&mut image, lt, a, b, FOOTHOLD_COLOR);
+            }
+        }
+        if overlay.ladders
+            && let Some(ladders) = map.try_get("ladderRope")?
+        {
+            for e in ladders.try_iter()? {
+                let x = e.try_get_default("x")?;
+                let a = Vector2D {
+                    x,
+                    y: e.try_get_default("y1")?,
+                };
+                let b = Vector2D {
+                    x,
+                    y: e.try_get_default("y2")?,
+                };
+                draw_line(&mut image, lt, a, b, LADDER_COLOR);
+            }
+        }
+        if overlay.portals
+            && let Some(portals) = map.try_get("portal")?
+        {
+            for e in portals.try_iter()? {
+                let at = Vector2D {
+                    x: e.try_get_default("x")?,
+                    y: e.try_get_default("y")?,
+                };
+                draw_marker(&mut image, lt, at, PORTAL_COLOR);
+            }
+        }
+        if overlay.life
+            && let Some(life) = map.try_get("life")?
+        {
+            for e in life.try_iter()? {
+                let color = match e.try_get_default::<String>("type")?.as_str() {
+                    "m" => MOB_COLOR,
+                    _ => NPC_COLOR,
+                };
+                let at = Vector2D {
+                    x: e.try_get_default("x")?,
+                    y: e.try_get_or_else("cy", || e.try_get_default("y"))?,
+                };
+                draw_marker(&mut image, lt, at, color);
+            }
+        }
+        Ok(image)
+    }
+}
+
+/// positions of a sprite at `pos` repeated every `step` to cover `start..end`.
+#[cfg(feature = "image")]
+fn repeat(pos: i32, step: i32, start: i32, end: i32) -> Vec<i32> {
+    if step <= 0 {
+        return vec![pos];
+    }
+    let first = start - (start - pos).rem_euclid(step);
+    (0..)
+        .map(|i| first + i * step)
+        .take_while(|p| *p < end)
+        .collect()
+}
+
+#[cfg(feature = "image")]
+fn put_pixel(image: &mut RgbaImage, x: i32, y: i32, color: Rgba<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+#[cfg(feature = "image")]
+fn draw_line(image: &mut RgbaImage, lt: Vector2D, a: Vector2D, b: Vector2D, color: Rgba<u8>) {
+    let (mut x, mut y) = (a.x - lt.x, a.y - lt.y);
+    let (x1, y1) = (b.x - lt.x, b.y - lt.y);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut err = dx + dy;
+    loop {
+        put_pixel(image, x, y, color);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+fn draw_marker(image: &mut RgbaImage, lt: Vector2D, at: Vector2D, color: Rgba<u8>) {
+    for y in -MARKER_SIZE..=MARKER_SIZE {
+        for x in -MARKER_SIZE..=MARKER_SIZE {
+            put_pixel(image, at.x - lt.x + x, at.y - lt.y + y, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::pack::tests::encoded_root;
+    use crate::reader::wizet::tests::TestValue::{self, Int, Props, Str};
+
+    // BGRA8888
+    const RED: [u8; 4] = [0, 0, 0xff, 0xff];
+    const GREEN: [u8; 4] = [0, 0xff, 0, 0xff];
+    const BLUE: [u8; 4] = [0xff, 0, 0, 0xff];
+
+    fn canvas(size: (i32, i32), bgra: [u8; 4], props: Vec<(&'static str, TestValue)>) -> TestValue {
+        TestValue::Canvas {
+            size,
+            format: 2,
+            data: bgra.repeat((size.0 * size.1) as usize),
+            props,
+        }
+    }
+
+    fn back(typ: i32, front: i32, x: i32) -> TestValue {
+        Props(vec![
+            ("bS", Str("grass")),
+            ("no", Str("0")),
+            ("x", Int(x)),
+            ("y", Int(0)),
+            ("type", Int(typ)),
+            ("front", Int(front)),
+        ])
+    }
+
+    fn obj(x: i32, z: i32) -> TestValue {
+        Props(vec![
+            ("oS", Str("house")),
+            ("l0", Str("a")),
+            ("l1", Str("b")),
+            ("l2", Str("c")),
+            ("x", Int(x)),
+            ("y", Int(0)),
+            ("z", Int(z)),
+        ])
+    }
+
+    fn foothold(a: (i32, i32), b: (i32, i32)) -> TestValue {
+        let segment = Props(vec![
+            ("x1", Int(a.0)),
+            ("y1", Int(a.1)),
+            ("x2", Int(b.0)),
+            ("y2", Int(b.1)),
+        ]);
+        Props(vec![("1", Props(vec![("1", Props(vec![("1", segment)]))]))])
+    }
+
+    fn vr(l: i32, t: i32, r: i32, b: i32) -> TestValue {
+        Props(vec![
+            ("VRLeft", Int(l)),
+            ("VRTop", Int(t)),
+            ("VRRight", Int(r)),
+            ("VRBottom", Int(b)),
+        ])
+    }
+
+    fn images() -> Vec<(String, TestValue)> {
+        let layer = Props(vec![
+            ("info", Props(vec![("tS", Str("wood"))])),
+            ("obj", Props(vec![("0", obj(20, 1)), ("1", obj(30, 0))])),
+            (
+                "tile",
+                Props(vec![(
+                    "0",
+                    Props(vec![
+                        ("u", Str("bsc")),
+                        ("no", Str("0")),
+                        ("x", Int(40)),
+                        ("y", Int(0)),
+                    ]),
+                )]),
+            ),
+        ]);
+        let obj_canvas = canvas((3, 3), GREEN, vec![("origin", TestValue::Vector(1, 1))]);
+        vec![
+            (
+                "Map/Back/grass.img".to_owned(),
+                Props(vec![(
+                    "back",
+                    Props(vec![("0", canvas((4, 2), RED, vec![]))]),
+                )]),
+            ),
+            (
+                "Map/Obj/house.img".to_owned(),
+                Props(vec![(
+                    "a",
+                    Props(vec![(
+                        "b",
+                        Props(vec![("c", Props(vec![("0", obj_canvas)]))]),
+                    )]),
+                )]),
+            ),
+            (
+                "Map/Tile/wood.img".to_owned(),
+                Props(vec![(
+                    "bsc",
+                    Props(vec![("0", canvas((5, 1), BLUE, vec![("z", Int(2))]))]),
+                )]),
+            ),
+            (
+                map_path(100),
+                Props(vec![
+                    (
+                        "back",
+                        Props(vec![("0", back(1, 0, 0)), ("1", back(0, 1, 10))]),
+                    ),
+                    ("0", layer),
+                    ("foothold", foothold((-50, 100), (150, 100))),
+                ]),
+            ),
+            (
+                map_path(200),
+                Props(vec![
+                    ("info", vr(0, 0, 12, 2)),
+                    ("back", Props(vec![("0", back(1, 0, 0))])),
+                ]),
+            ),
+            (
+                map_path(300),
+                Props(vec![
+                    ("info", vr(0, 0, 12, 2)),
+                    ("back", Props(vec![("0", back(0, 0, 0))])),
+                ]),
+            ),
+            // a single point
+            (
+                map_path(400),
+                Props(vec![("foothold", foothold((5, 5), (5, 5)))]),
+            ),
+            (
+                map_path(500),
+                Props(vec![("info", vr(-100000, -100000, 100000, 100000))]),
+            ),
+        ]
+    }
+
+    fn root(tag: &str) -> (std::path::PathBuf, EntryCache) {
+        encoded_root(tag, &images())
+    }
+
+    #[test]
+    fn sprites_in_order() {
+        let (dir, root) = root("map-sprites");
+        let renderer = MapRenderer::new(&root);
+        let sprites = renderer.sprites(100).unwrap();
+        let positions = sprites
+            .iter()
+            .map(|s| (s.position.x, s.position.y))
+            .collect::<Vec<_>>();
+        // back, objects by z, tiles, then front
+        assert_eq!(positions, [(0, 0), (29, -1), (19, -1), (40, 0), (10, 0)]);
+        assert_eq!(sprites[0].tile, Vector2D { x: 4, y: 0 });
+        assert_eq!(sprites[4].tile, Vector2D::default());
+        assert!(sprites.iter().all(|s| s.alpha == OPAQUE));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bounds_of_sprites_and_footholds() {
+        let (dir, root) = root("map-bounds");
+        let renderer = MapRenderer::new(&root);
+        let sprites = renderer.sprites(100).unwrap();
+        // the tiled back is left out
+        assert_eq!(
+            renderer.bounds(100, &sprites).unwrap(),
+            (Vector2D { x: -50, y: -1 }, Vector2D { x: 150, y: 100 })
+        );
+        let sprites = renderer.sprites(200).unwrap();
+        assert_eq!(
+            renderer.bounds(200, &sprites).unwrap(),
+            (Vector2D { x: 0, y: 0 }, Vector2D { x: 12, y: 2 })
+        );
+        assert!(matches!(renderer.sprites(1), Err(Error::InvalidArgument)));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_tiled_back() {
+        let (dir, root) = root("map-tiling");
+        let renderer = MapRenderer::new(&root);
+        let red = Rgba([0xff, 0, 0, 0xff]);
+
+        let image = renderer.render(200, MapOverlay::default()).unwrap();
+        assert_eq!(image.dimensions(), (12, 2));
+        assert!(image.pixels().all(|p| *p == red));
+
+        let image = renderer.render(300, MapOverlay::default()).unwrap();
+        assert_eq!(*image.get_pixel(3, 1), red);
+        assert_eq!(*image.get_pixel(4, 1), Rgba([0; 4]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_rejects_bounds() {
+        let (dir, root) = root("map-render-bounds");
+        let renderer = MapRenderer::new(&root);
+        for id in [400, 500] {
+            assert!(matches!(
+                renderer.render(id, MapOverlay::default()),
+                Err(Error::InvalidArgument)
+            ));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
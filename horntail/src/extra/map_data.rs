@@ -0,0 +1,574 @@
+use crate::extra::EntryCache;
+use crate::extra::map::{map_path, numbered};
+use crate::{Error, Vector2D};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+const IMAGE_EXTENSION: &str = ".img";
+/// `returnMap` and `tm` of nowhere.
+pub const NO_MAP: u32 = 999999999;
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct MapInfo {
+    pub vr_left: Option<i32>,
+    pub vr_top: Option<i32>,
+    pub vr_right: Option<i32>,
+    pub vr_bottom: Option<i32>,
+    pub return_map: u32,
+    pub forced_return: u32,
+    pub field_limit: u64,
+    pub town: bool,
+    pub bgm: String,
+}
+
+impl TryFrom<&EntryCache> for MapInfo {
+    type Error = Error;
+
+    fn try_from(info: &EntryCache) -> Result<Self, Self::Error> {
+        Ok(MapInfo {
+            vr_left: info.try_get_value("VRLeft")?,
+            vr_top: info.try_get_value("VRTop")?,
+            vr_right: info.try_get_value("VRRight")?,
+            vr_bottom: info.try_get_value("VRBottom")?,
+            return_map: info.try_get_or_else("returnMap", || Ok(NO_MAP))?,
+            forced_return: info.try_get_or_else("forcedReturn", || Ok(NO_MAP))?,
+            field_limit: info.try_get_default("fieldLimit")?,
+            town: info.try_get_default("town")?,
+            bgm: info.try_get_default("bgm")?,
+        })
+    }
+}
+
+/// `foothold/<layer>/<group>/<id>`, `prev` and `next` are `0` at the ends.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Foothold {
+    pub id: u32,
+    pub layer: u32,
+    pub group: u32,
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+    pub prev: u32,
+    pub next: u32,
+    pub force: i32,
+    pub forbid_fall_down: bool,
+}
+
+impl Foothold {
+    /// vertical footholds are walls.
+    #[inline]
+    pub fn is_wall(&self) -> bool {
+        self.x1 == self.x2
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Portal {
+    pub id: u32,
+    /// `pn`
+    pub name: String,
+    /// `pt`
+    pub kind: i32,
+    pub x: i32,
+    pub y: i32,
+    /// `tm`, [`NO_MAP`] for none.
+    pub target_map: u32,
+    /// `tn`
+    pub target_name: String,
+    pub script: Option<String>,
+}
+
+impl Portal {
+    #[inline]
+    pub fn has_target(&self) -> bool {
+        self.target_map != NO_MAP
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum LifeKind {
+    Mob,
+    Npc,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Life {
+    pub kind: LifeKind,
+    pub id: u32,
+    pub x: i32,
+    /// y on the foothold.
+    pub cy: i32,
+    /// foothold id.
+    pub fh: u32,
+    /// left and right of the move range.
+    pub rx0: i32,
+    pub rx1: i32,
+    pub flip: bool,
+    pub hide: bool,
+    /// respawn delay in seconds, mob only.
+    pub mob_time: i32,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct LadderRope {
+    pub id: u32,
+    pub x: i32,
+    pub y1: i32,
+    pub y2: i32,
+    /// ladder or rope.
+    pub ladder: bool,
+    /// can climb up to the foothold above.
+    pub upper_foothold: bool,
+    pub page: i32,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Seat {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// broken references found by [`MapData::issues`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum MapIssue {
+    DanglingPrev { foothold: u32, prev: u32 },
+    DanglingNext { foothold: u32, next: u32 },
+    DanglingLifeFoothold { life: u32, fh: u32 },
+    MissingTargetMap { portal: u32, map: u32 },
+}
+
+impl Display for MapIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapIssue::DanglingPrev { foothold, prev } => {
+                write!(f, "foothold {foothold} prev {prev} not exists")
+            }
+            MapIssue::DanglingNext { foothold, next } => {
+                write!(f, "foothold {foothold} next {next} not exists")
+            }
+            MapIssue::DanglingLifeFoothold { life, fh } => {
+                write!(f, "life {life} foothold {fh} not exists")
+            }
+            MapIssue::MissingTargetMap { portal, map } => {
+                write!(f, "portal {portal} target map {map} not exists")
+            }
+        }
+    }
+}
+
+/// physics of `Map/Map/MapN/<id>.img`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct MapData {
+    pub id: u32,
+    pub info: MapInfo,
+    pub footholds: Vec<Foothold>,
+    pub portals: Vec<Portal>,
+    pub life: Vec<Life>,
+    pub ladder_ropes: Vec<LadderRope>,
+    pub seats: Vec<Seat>,
+}
+
+impl MapData {
+    /// load map `id` from `root` which holds `Map`.
+    pub fn try_load(root: &EntryCache, id: u32) -> Result<MapData, Error> {
+        root.try_get_by_path(map_path(id))?
+            .ok_or(Error::InvalidArgument)?
+            .try_to()
+    }
+
+    #[inline]
+    pub fn foothold(&self, id: u32) -> Option<&Foothold> {
+        self.footholds.iter().find(|fh| fh.id == id)
+    }
+
+    /// dangling foothold links and portals to maps `exists` returns false.
+    pub fn issues<F: Fn(u32) -> bool>(&self, exists: F) -> Vec<MapIssue> {
+        let ids = self
+            .footholds
+            .iter()
+            .map(|fh| fh.id)
+            .collect::<ahash::HashSet<_>>();
+        let mut issues = vec![];
+        for fh in self.footholds.iter() {
+            if fh.prev != 0 && !ids.contains(&fh.prev) {
+                issues.push(MapIssue::DanglingPrev {
+                    foothold: fh.id,
+                    prev: fh.prev,
+                });
+            }
+            if fh.next != 0 && !ids.contains(&fh.next) {
+                issues.push(MapIssue::DanglingNext {
+                    foothold: fh.id,
+                    next: fh.next,
+                });
+            }
+        }
+        for (i, life) in self.life.iter().enumerate() {
+            if life.fh != 0 && !ids.contains(&life.fh) {
+                issues.push(MapIssue::DanglingLifeFoothold {
+                    life: i as u32,
+                    fh: life.fh,
+                });
+            }
+        }
+        for portal in self.portals.iter() {
+            if portal.has_target() && portal.target_map != self.id && !exists(portal.target_map) {
+                issues.push(MapIssue::MissingTargetMap {
+                    portal: portal.id,
+                    map: portal.target_map,
+                });
+            }
+        }
+        issues
+    }
+
+    /// [`MapData::issues`] with target maps looked up in `root`.
+    pub fn try_validate(&self, root: &EntryCache) -> Result<Vec<MapIssue>, Error> {
+        let mut missing = vec![];
+        for portal in self.portals.iter().filter(|p| p.has_target()) {
+            if root.try_get_by_path(map_path(portal.target_map))?.is_none() {
+                missing.push(portal.target_map);
+            }
+        }
+        Ok(self.issues(|id| !missing.contains(&id)))
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Unexpected(Box::new(e)))
+    }
+}
+
+impl TryFrom<&EntryCache> for MapData {
+    type Error = Error;
+
+    /// `entry` is the map image, `id` comes from its name.
+    fn try_from(entry: &EntryCache) -> Result<Self, Self::Error> {
+        let id = entry
+            .name()
+            .trim_end_matches(IMAGE_EXTENSION)
+            .parse::<u32>()
+            .map_err(|_| Error::InvalidArgument)?;
+        let mut map = MapData {
+            id,
+            ..Default::default()
+        };
+        if let Some(info) = entry.try_get("info")? {
+            map.info = info.try_to()?;
+        }
+
+        if let Some(foothold) = entry.try_get("foothold")? {
+            for layer in foothold.try_iter()? {
+                for group in layer.try_iter()? {
+                    for e in group.try_iter()? {
+                        map.footholds.push(Foothold {
+                            id: parse_name(e)?,
+                            layer: parse_name(layer)?,
+                            group: parse_name(group)?,
+                            x1: e.try_get_default("x1")?,
+                            y1: e.try_get_default("y1")?,
+                            x2: e.try_get_default("x2")?,
+                            y2: e.try_get_default("y2")?,
+                            prev: e.try_get_default("prev")?,
+                            next: e.try_get_default("next")?,
+                            force: e.try_get_default("force")?,
+                            forbid_fall_down: e.try_get_default("forbidFallDown")?,
+                        });
+                    }
+                }
+            }
+            map.footholds.sort_by_key(|fh| fh.id);
+        }
+
+        if let Some(portal) = entry.try_get("portal")? {
+            for (i, e) in numbered(portal)? {
+                map.portals.push(Portal {
+                    id: i as u32,
+                    name: e.try_get_default("pn")?,
+                    kind: e.try_get_default("pt")?,
+                    x: e.try_get_default("x")?,
+                    y: e.try_get_default("y")?,
+                    target_map: e.try_get_or_else("tm", || Ok(NO_MAP))?,
+                    target_name: e.try_get_default("tn")?,
+                    script: e
+                        .try_get_value::<String>("script")?
+                        .filter(|s| !s.is_empty()),
+                });
+            }
+        }
+
+        if let Some(life) = entry.try_get("life")? {
+            for (_, e) in numbered(life)? {
+                let kind = match e.try_get_default::<String>("type")?.as_str() {
+                    "m" => LifeKind::Mob,
+                    "n" => LifeKind::Npc,
+                    _ => continue,
+                };
+                map.life.push(Life {
+                    kind,
+                    id: e.try_get_default("id")?,
+                    x: e.try_get_default("x")?,
+                    cy: e.try_get_default("cy")?,
+                    fh: e.try_get_default("fh")?,
+                    rx0: e.try_get_default("rx0")?,
+                    rx1: e.try_get_default("rx1")?,
+                    flip: e.try_get_default("f")?,
+                    hide: e.try_get_default("hide")?,
+                    mob_time: e.try_get_default("mobTime")?,
+                });
+            }
+        }
+
+        if let Some(ladders) = entry.try_get("ladderRope")? {
+            for (i, e) in numbered(ladders)? {
+                map.ladder_ropes.push(LadderRope {
+                    id: i as u32,
+                    x: e.try_get_default("x")?,
+                    y1: e.try_get_default("y1")?,
+                    y2: e.try_get_default("y2")?,
+                    ladder: e.try_get_default("l")?,
+                    upper_foothold: e.try_get_default("uf")?,
+                    page: e.try_get_default("page")?,
+                });
+            }
+        }
+
+        if let Some(seats) = entry.try_get("seat")? {
+            for (i, e) in numbered(seats)? {
+                let v = e.try_to::<Vector2D>()?;
+                map.seats.push(Seat {
+                    id: i as u32,
+                    x: v.x,
+                    y: v.y,
+                });
+            }
+        }
+        Ok(map)
+    }
+}
+
+#[inline]
+fn parse_name(entry: &EntryCache) -> Result<u32, Error> {
+    entry
+        .name()
+        .parse()
+        .map_err(|_| Error::UnexpectedData(format!("invalid id `{}`", entry.name())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::pack::tests::encoded_root;
+    use crate::reader::wizet::tests::TestValue::{self, Int, Props, Str};
+
+    const ID: u32 = 100000000;
+
+    fn props(props: &[(&'static str, i32)]) -> TestValue {
+        Props(props.iter().map(|(k, v)| (*k, Int(*v))).collect())
+    }
+
+    fn with(mut value: TestValue, name: &'static str, extra: TestValue) -> TestValue {
+        if let Props(props) = &mut value {
+            props.push((name, extra));
+        }
+        value
+    }
+
+    fn map() -> TestValue {
+        let footholds = Props(vec![
+            (
+                "10",
+                props(&[("x1", 0), ("y1", 0), ("x2", 100), ("y2", 0), ("next", 11)]),
+            ),
+            (
+                "11",
+                props(&[
+                    ("x1", 100),
+                    ("y1", 0),
+                    ("x2", 100),
+                    ("y2", 50),
+                    ("prev", 10),
+                    ("next", 12),
+                ]),
+            ),
+            ("13", props(&[("prev", 99), ("forbidFallDown", 1)])),
+        ]);
+        Props(vec![
+            (
+                "info",
+                with(
+                    with(
+                        props(&[("returnMap", ID as i32), ("town", 1), ("VRLeft", -100)]),
+                        "bgm",
+                        Str("Bgm00/FloralLife"),
+                    ),
+                    "fieldLimit",
+                    Int(0x40),
+                ),
+            ),
+            (
+                "foothold",
+                Props(vec![("1", Props(vec![("2", footholds)]))]),
+            ),
+            (
+                "portal",
+                Props(vec![
+                    ("0", with(props(&[("pt", 0), ("x", 5)]), "pn", Str("sp"))),
+                    (
+                        "1",
+                        with(
+                            props(&[("pt", 2), ("tm", ID as i32 + 1)]),
+                            "tn",
+                            Str("west00"),
+                        ),
+                    ),
+                    ("2", props(&[("pt", 2), ("tm", ID as i32 + 2)])),
+                    ("3", props(&[("pt", 2), ("tm", ID as i32)])),
+                    ("4", with(props(&[("pt", 3)]), "script", Str(""))),
+                ]),
+            ),
+            (
+                "life",
+                Props(vec![
+                    (
+                        "0",
+                        with(
+                            props(&[
+                                ("id", 100100),
+                                ("x", 10),
+                                ("cy", 0),
+                                ("fh", 10),
+                                ("rx0", -10),
+                                ("rx1", 30),
+                                ("mobTime", 5),
+                            ]),
+                            "type",
+                            Str("m"),
+                        ),
+                    ),
+                    (
+                        "1",
+                        with(
+                            props(&[("id", 9000000), ("fh", 77), ("f", 1)]),
+                            "type",
+                            Str("n"),
+                        ),
+                    ),
+                    ("2", with(props(&[("id", 1)]), "type", Str("r"))),
+                ]),
+            ),
+            (
+                "ladderRope",
+                Props(vec![(
+                    "0",
+                    props(&[
+                        ("x", 50),
+                        ("y1", -100),
+                        ("y2", 0),
+                        ("l", 1),
+                        ("uf", 1),
+                        ("page", 1),
+                    ]),
+                )]),
+            ),
+            ("seat", Props(vec![("0", TestValue::Vector(3, 4))])),
+        ])
+    }
+
+    fn root(tag: &str) -> (std::path::PathBuf, EntryCache) {
+        encoded_root(
+            tag,
+            &[(map_path(ID), map()), (map_path(ID + 1), Props(vec![]))],
+        )
+    }
+
+    #[test]
+    fn parse_map() {
+        let (dir, root) = root("map-data-parse");
+        let map = MapData::try_load(&root, ID).unwrap();
+        assert_eq!(map.id, ID);
+        assert_eq!(map.info.return_map, ID);
+        assert_eq!(map.info.forced_return, NO_MAP);
+        assert_eq!(map.info.vr_left, Some(-100));
+        assert_eq!(map.info.vr_right, None);
+        assert_eq!(map.info.field_limit, 0x40);
+        assert!(map.info.town);
+        assert_eq!(map.info.bgm, "Bgm00/FloralLife");
+
+        let ids = map.footholds.iter().map(|fh| fh.id).collect::<Vec<_>>();
+        assert_eq!(ids, [10, 11, 13]);
+        let fh = map.foothold(11).unwrap();
+        assert_eq!((fh.layer, fh.group, fh.prev, fh.next), (1, 2, 10, 12));
+        assert!(fh.is_wall());
+        assert!(!map.foothold(10).unwrap().is_wall());
+        assert!(map.foothold(13).unwrap().forbid_fall_down);
+
+        assert_eq!(map.portals.len(), 5);
+        assert_eq!(map.portals[0].name, "sp");
+        assert!(!map.portals[0].has_target());
+        assert_eq!(map.portals[1].target_map, ID + 1);
+        assert_eq!(map.portals[1].target_name, "west00");
+        assert_eq!(map.portals[4].script, None);
+
+        // unknown life types are skipped
+        assert_eq!(map.life.len(), 2);
+        let mob = &map.life[0];
+        assert_eq!((mob.kind, mob.id, mob.fh), (LifeKind::Mob, 100100, 10));
+        assert_eq!((mob.rx0, mob.rx1, mob.mob_time), (-10, 30, 5));
+        assert_eq!(map.life[1].kind, LifeKind::Npc);
+        assert!(map.life[1].flip);
+
+        let ladder = &map.ladder_ropes[0];
+        assert_eq!((ladder.x, ladder.y1, ladder.y2), (50, -100, 0));
+        assert!(ladder.ladder && ladder.upper_foothold);
+        assert_eq!((map.seats[0].x, map.seats[0].y), (3, 4));
+
+        assert!(matches!(
+            MapData::try_load(&root, 1),
+            Err(Error::InvalidArgument)
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn validate_references() {
+        let (dir, root) = root("map-data-validate");
+        let map = MapData::try_load(&root, ID).unwrap();
+        let dangling = [
+            MapIssue::DanglingNext {
+                foothold: 11,
+                next: 12,
+            },
+            MapIssue::DanglingPrev {
+                foothold: 13,
+                prev: 99,
+            },
+            MapIssue::DanglingLifeFoothold { life: 1, fh: 77 },
+        ];
+        assert_eq!(map.issues(|_| true), dangling);
+
+        let mut issues = dangling.to_vec();
+        issues.push(MapIssue::MissingTargetMap {
+            portal: 2,
+            map: ID + 2,
+        });
+        assert_eq!(map.try_validate(&root).unwrap(), issues);
+        assert_eq!(
+            issues[3].to_string(),
+            "portal 2 target map 100000002 not exists"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,326 @@
+use crate::Error;
+use crate::extra::item::{csv_field, lenient, numeric_fields};
+use crate::extra::map::numbered;
+use crate::extra::{EntryCache, StringCategory, StringIndex};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const IMAGE_EXTENSION: &str = ".img";
+const MOB: &str = "Mob";
+const INFO: &str = "info";
+const MOB_SKILL: &str = "Skill/MobSkill.img";
+const MAX_LINK_DEPTH: usize = 8;
+
+/// `info/skill/<n>` of mob with the stats of `Skill/MobSkill.img/<id>/level/<level>`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct MobSkill {
+    pub id: u32,
+    pub level: u32,
+    /// index of `skill<n>` action.
+    pub action: i32,
+    pub stats: BTreeMap<String, i64>,
+}
+
+/// `Mob/<id>.img`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct MobData {
+    pub id: u32,
+    /// from `String/Mob.img` if given.
+    pub name: Option<String>,
+    pub level: u32,
+    pub max_hp: i64,
+    pub max_mp: i64,
+    pub exp: i64,
+    pub pa_damage: i32,
+    pub ma_damage: i32,
+    pub pd_damage: i32,
+    pub md_damage: i32,
+    pub acc: i32,
+    pub eva: i32,
+    pub speed: i32,
+    /// pairs of element and level like `F2I3`, see [`MobData::elements`].
+    pub elem_attr: String,
+    pub boss: bool,
+    pub undead: bool,
+    pub body_attack: bool,
+    /// `info/link`, the mob sprites are shared from.
+    pub link: Option<u32>,
+    pub skills: Vec<MobSkill>,
+    /// actions like `stand`, `move`, `hit1`, `die1`, `attack1` of this mob or
+    /// the linked one.
+    pub actions: Vec<String>,
+}
+
+impl MobData {
+    /// load mob `id` from `root` which holds `Mob` and `Skill`.
+    pub fn try_load(root: &EntryCache, id: u32) -> Result<MobData, Error> {
+        let image = root
+            .try_get_by_path(mob_path(id))?
+            .ok_or(Error::InvalidArgument)?;
+        Self::try_from_entry(root, id, image)
+    }
+
+    fn try_from_entry(root: &EntryCache, id: u32, image: &EntryCache) -> Result<MobData, Error> {
+        let mut mob = MobData {
+            id,
+            ..Default::default()
+        };
+        if let Some(e) = image.try_get(INFO)? {
+            mob.level = lenient(e, "level")?;
+            mob.max_hp = lenient(e, "maxHP")?;
+            mob.max_mp = lenient(e, "maxMP")?;
+            mob.exp = lenient(e, "exp")?;
+            mob.pa_damage = lenient(e, "PADamage")?;
+            mob.ma_damage = lenient(e, "MADamage")?;
+            mob.pd_damage = lenient(e, "PDDamage")?;
+            mob.md_damage = lenient(e, "MDDamage")?;
+            mob.acc = lenient(e, "acc")?;
+            mob.eva = lenient(e, "eva")?;
+            mob.speed = lenient(e, "speed")?;
+            mob.elem_attr = lenient(e, "elemAttr")?;
+            mob.boss = lenient(e, "boss")?;
+            mob.undead = lenient(e, "undead")?;
+            mob.body_attack = lenient(e, "bodyAttack")?;
+            let link = lenient::<u32>(e, "link")?;
+            mob.link = (link != 0).then_some(link);
+
+            if let Some(skills) = e.try_get("skill")? {
+                let skill_root = root.try_get_by_path(MOB_SKILL)?;
+                for (_, s) in numbered(skills)? {
+                    let mut skill = MobSkill {
+                        id: lenient(s, "skill")?,
+                        level: lenient(s, "level")?,
+                        action: lenient(s, "action")?,
+                        stats: BTreeMap::new(),
+                    };
+                    if let Some(skill_root) = skill_root
+                        && let Some(level) = skill_root
+                            .try_get_by_path(format!("{}/level/{}", skill.id, skill.level))?
+                    {
+                        skill.stats = numeric_fields(level)?;
+                    }
+                    mob.skills.push(skill);
+                }
+            }
+        }
+
+        let sprites = match mob.link {
+            Some(link) => Self::resolve_link(root, id, link)?.unwrap_or(image),
+            None => image,
+        };
+        mob.actions = sprites
+            .try_iter()?
+            .filter(|e| e.name() != INFO && e.has_children())
+            .map(|e| e.name().to_owned())
+            .collect();
+        mob.actions.sort();
+        Ok(mob)
+    }
+
+    /// image of `link` with the chain of links followed, `None` when an image
+    /// is missing or the chain loops back to a mob already visited.
+    fn resolve_link(
+        root: &EntryCache,
+        id: u32,
+        mut link: u32,
+    ) -> Result<Option<&EntryCache>, Error> {
+        let mut visited = vec![id];
+        for _ in 0..MAX_LINK_DEPTH {
+            let Some(image) = root.try_get_by_path(mob_path(link))? else {
+                return Ok(None);
+            };
+            visited.push(link);
+            match image
+                .try_get_by_path("info/link")?
+                .and_then(|e| e.try_to::<u32>().ok())
+            {
+                Some(next) if next != 0 && next != link => {
+                    if visited.contains(&next) {
+                        return Ok(None);
+                    }
+                    link = next;
+                }
+                _ => return Ok(Some(image)),
+            }
+        }
+        Ok(None)
+    }
+
+    /// `elemAttr` as element and level pairs, level `1` is immune, `2` is
+    /// strong and `3` is weak.
+    pub fn elements(&self) -> Vec<(char, u8)> {
+        let chars = self.elem_attr.chars().collect::<Vec<_>>();
+        chars
+            .chunks_exact(2)
+            .filter_map(|pair| Some((pair[0], pair[1].to_digit(10)? as u8)))
+            .collect()
+    }
+}
+
+#[inline]
+fn mob_path(id: u32) -> String {
+    format!("{MOB}/{id:07}{IMAGE_EXTENSION}")
+}
+
+/// every mob of `Mob` in order of id.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct MobTable {
+    pub mobs: Vec<MobData>,
+}
+
+impl MobTable {
+    /// `root` holds `Mob` and `Skill`, names are taken from `strings` if given.
+    pub fn try_load(root: &EntryCache, strings: Option<&StringIndex>) -> Result<MobTable, Error> {
+        let mut mobs = vec![];
+        if let Some(mob) = root.try_get(MOB)? {
+            for image in mob.try_iter()? {
+                let Ok(id) = image.name().trim_end_matches(IMAGE_EXTENSION).parse() else {
+                    continue;
+                };
+                let mut data = MobData::try_from_entry(root, id, image)?;
+                data.name = strings
+                    .and_then(|s| s.name(&StringCategory::Mob, id))
+                    .map(|s| s.to_owned());
+                mobs.push(data);
+            }
+        }
+        mobs.sort_by_key(|m| m.id);
+        Ok(MobTable { mobs })
+    }
+
+    /// mobs as CSV with a header line, skills are written as `id:level;...`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "id,name,level,max_hp,max_mp,exp,pa_damage,ma_damage,pd_damage,md_damage,\
+             acc,eva,speed,elem_attr,boss,undead,body_attack,link,skills,actions\n",
+        );
+        for m in self.mobs.iter() {
+            let skills = m
+                .skills
+                .iter()
+                .map(|s| format!("{}:{}", s.id, s.level))
+                .collect::<Vec<_>>()
+                .join(";");
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                m.id,
+                csv_field(m.name.as_deref().unwrap_or_default()),
+                m.level,
+                m.max_hp,
+                m.max_mp,
+                m.exp,
+                m.pa_damage,
+                m.ma_damage,
+                m.pd_damage,
+                m.md_damage,
+                m.acc,
+                m.eva,
+                m.speed,
+                csv_field(&m.elem_attr),
+                m.boss,
+                m.undead,
+                m.body_attack,
+                m.link.map(|l| l.to_string()).unwrap_or_default(),
+                csv_field(&skills),
+                csv_field(&m.actions.join(";")),
+            );
+        }
+        csv
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Unexpected(Box::new(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra::pack::tests::encoded_root;
+    use crate::reader::wizet::tests::TestValue::{self, Int, Props, Str};
+
+    fn mob(info: Vec<(&'static str, TestValue)>, actions: &[&'static str]) -> TestValue {
+        let mut props = vec![(INFO, Props(info))];
+        props.extend(actions.iter().map(|a| (*a, Props(vec![("0", Int(0))]))));
+        Props(props)
+    }
+
+    fn actions(root: &EntryCache, id: u32) -> Vec<String> {
+        MobData::try_load(root, id).unwrap().actions
+    }
+
+    #[test]
+    fn follow_links() {
+        let (dir, root) = encoded_root(
+            "mob-links",
+            &[
+                (
+                    mob_path(100100),
+                    mob(
+                        vec![("level", Int(5)), ("elemAttr", Str("F2I3"))],
+                        &["stand", "move"],
+                    ),
+                ),
+                (mob_path(100101), mob(vec![("link", Int(100100))], &[])),
+                (
+                    mob_path(100102),
+                    mob(vec![("link", Int(100101))], &["hit1"]),
+                ),
+                // a cycle falls back to the own image
+                (
+                    mob_path(100103),
+                    mob(vec![("link", Int(100104))], &["die1"]),
+                ),
+                (mob_path(100104), mob(vec![("link", Int(100103))], &[])),
+                (
+                    mob_path(100105),
+                    mob(vec![("link", Int(9999999))], &["fly"]),
+                ),
+            ],
+        );
+
+        let snail = MobData::try_load(&root, 100100).unwrap();
+        assert_eq!((snail.level, snail.link), (5, None));
+        assert_eq!(snail.actions, ["move", "stand"]);
+        assert_eq!(actions(&root, 100101), ["move", "stand"]);
+        assert_eq!(actions(&root, 100102), ["move", "stand"]);
+        assert_eq!(actions(&root, 100103), ["die1"]);
+        assert_eq!(actions(&root, 100104), [] as [&str; 0]);
+        assert_eq!(actions(&root, 100105), ["fly"]);
+        assert_eq!(
+            MobData::try_load(&root, 100105).unwrap().link,
+            Some(9999999)
+        );
+
+        let table = MobTable::try_load(&root, None).unwrap();
+        assert_eq!(table.mobs.len(), 6);
+        assert_eq!(table.mobs[3].actions, ["die1"]);
+        assert!(matches!(
+            MobData::try_load(&root, 1),
+            Err(Error::InvalidArgument)
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn elements() {
+        let elements = |elem_attr: &str| {
+            MobData {
+                elem_attr: elem_attr.to_owned(),
+                ..Default::default()
+            }
+            .elements()
+        };
+        assert_eq!(elements("F2I3"), [('F', 2), ('I', 3)]);
+        assert_eq!(elements("H1S"), [('H', 1)]);
+        assert_eq!(elements("FXL3"), [('L', 3)]);
+        assert_eq!(elements(""), []);
+    }
+}
@@ -19,11 +19,55 @@
 //! let _ = cache.get("Character");
 //! ```
 //!
+//! Stack `Data` bundles and `Packs` files, packs take priority.
+//!
+//! ```no_run
+//! use horntail::crypto::{MapleCipher, MapleTableNone, MapleVersion};
+//! use horntail::extra::Entry;
+//!
+//! let version = MapleVersion::from(217);
+//! let data = Entry::from_path("Data/Base", MapleTableNone.into_boxed(), version, false).unwrap();
+//! let packs = Entry::from_packs("Packs", version).unwrap();
+//! let root = Entry::overlay("Root", vec![data, packs]);
+//!
+//! for e in root.iter() {
+//!     println!("{} from layer {:?}", e.name(), e.layer());
+//! }
+//! ```
+//!
+mod animation;
+mod avatar;
 mod bundle;
 mod cache;
+mod diff;
 mod entry;
 mod entry_ext;
+mod item;
 mod iter;
+mod map;
+mod map_data;
+mod mob;
+mod pack;
+mod quest;
+mod schema;
+mod skill;
+mod string_index;
 
+pub use animation::{Animation, Frame};
+pub use avatar::{Avatar, AvatarLayer, AvatarRenderer};
 pub use cache::EntryCache;
+pub use diff::{Change, Diff, DiffOptions};
 pub use entry::{Entry, EntryPrimitive, EntryValue};
+pub use item::{EquipInfo, ItemInfo, ItemTable, SetItem};
+pub use map::{MapOverlay, MapRenderer, MapSprite};
+pub use map_data::{
+    Foothold, LadderRope, Life, LifeKind, MapData, MapInfo, MapIssue, NO_MAP, Portal, Seat,
+};
+pub use mob::{MobData, MobSkill, MobTable};
+pub use quest::{
+    IdCount, NpcInfo, Quest, QuestPhase, QuestRequirement, QuestReward, QuestState, QuestTable,
+    RewardItem,
+};
+pub use schema::{PathSchema, Schema};
+pub use skill::{Formula, SkillLevels};
+pub use string_index::{StringCategory, StringEntry, StringIndex};
@@ -0,0 +1,266 @@
+use crate::crypto::MapleTableNone;
+use crate::extra::iter::ComponentIter;
+use crate::reader::{BinaryAccessor, BinaryBuilder, PackEntries, PackFile, StringDecoder};
+use crate::{AccessorBuilder, AccessorOpt, Error, Image, TryFromBuilder};
+use memmap2::Mmap;
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+struct PackSource {
+    mmap: Mmap,
+    entries: PackEntries,
+}
+
+pub(crate) struct PackBundle {
+    packs: Vec<Rc<PackSource>>,
+    decoder: StringDecoder,
+}
+
+pub(crate) enum PackChild {
+    Folder(String),
+    Image(String, Image, PackImage),
+}
+
+/// image of a pack entry, the body is decrypted on first open.
+pub(crate) struct PackImage {
+    source: Rc<PackSource>,
+    index: usize,
+    decoder: StringDecoder,
+    builder: OnceCell<Box<dyn AccessorBuilder>>,
+}
+
+impl PackImage {
+    /// drop the decrypted body, it is built again with `decoder` on next open.
+    #[inline]
+    pub fn set_string_decoder(&mut self, decoder: StringDecoder) {
+        self.decoder = decoder;
+        self.builder = OnceCell::new();
+    }
+
+    pub fn try_builder(&self) -> Result<&dyn AccessorBuilder, Error> {
+        if let Some(builder) = self.builder.get() {
+            return Ok(builder.as_ref());
+        }
+        let mut accessor = BinaryAccessor::new(MapleTableNone, self.source.mmap.as_ref());
+        let data = self.source.entries[self.index].decrypt_from(&mut accessor)?;
+        let builder = BinaryBuilder::new(MapleTableNone, data)
+            .with_decoder(self.decoder)
+            .into_boxed();
+        Ok(self.builder.get_or_init(|| builder).as_ref())
+    }
+}
+
+impl PackChild {
+    #[inline]
+    fn name(&self) -> &str {
+        match self {
+            PackChild::Folder(name) => name,
+            PackChild::Image(name, _, _) => name,
+        }
+    }
+}
+
+impl PackBundle {
+    /// `path` is either a single pack file or a directory contains pack files.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Option<PackBundle>, Error> {
+        let path = path.as_ref();
+        let files = if path.is_dir() {
+            let mut files = std::fs::read_dir(path)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && is_pack_file(p))
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        } else if is_pack_file(path) {
+            vec![path.to_path_buf()]
+        } else {
+            vec![]
+        };
+
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(PackBundle::from_files(files)?))
+    }
+
+    fn from_files(files: Vec<PathBuf>) -> Result<PackBundle, Error> {
+        let mut packs = Vec::with_capacity(files.len());
+        for f in files {
+            let file = PackFile::new(f)?;
+            packs.push(Rc::new(PackSource {
+                mmap: file.source().open()?,
+                entries: file.entries()?,
+            }));
+        }
+        Ok(PackBundle {
+            packs,
+            decoder: StringDecoder::default(),
+        })
+    }
+
+    /// same pack files with other `decoder`, the files are shared, not reloaded.
+    #[inline]
+    pub fn with_string_decoder(&self, decoder: StringDecoder) -> PackBundle {
+        PackBundle {
+            packs: self.packs.clone(),
+            decoder,
+        }
+    }
+
+    /// children directly under `prefix`, pack files loaded later replace the
+    /// images or folders with same name of earlier ones. only image headers
+    /// are decrypted.
+    pub fn children(&self, prefix: &[String]) -> Result<Vec<PackChild>, Error> {
+        let mut index = ahash::HashMap::<String, usize>::default();
+        let mut children = Vec::new();
+        for pack in self.packs.iter() {
+            let mut accessor = BinaryAccessor::new(MapleTableNone, pack.mmap.as_ref());
+            for (i, entry) in pack.entries.iter().enumerate() {
+                let mut components = ComponentIter::from(Path::new(&entry.name).components());
+                if !prefix.iter().all(|p| components.next() == Some(p.as_str())) {
+                    continue;
+                }
+                let Some(name) = components.next() else {
+                    continue;
+                };
+
+                let child = if components.next().is_some() {
+                    // folders of the same name are one, an image is replaced
+                    if index
+                        .get(name)
+                        .is_some_and(|i| matches!(children[*i], PackChild::Folder(_)))
+                    {
+                        continue;
+                    }
+                    PackChild::Folder(name.to_owned())
+                } else {
+                    let header = entry.decrypt_header_from(&mut accessor)?;
+                    let builder = BinaryBuilder::new(MapleTableNone, header);
+                    let mut image = Image::try_from_builder(AccessorOpt::default(), &builder)?;
+                    image.size = entry.size as usize;
+                    PackChild::Image(
+                        name.to_owned(),
+                        image,
+                        PackImage {
+                            source: pack.clone(),
+                            index: i,
+                            decoder: self.decoder,
+                            builder: OnceCell::new(),
+                        },
+                    )
+                };
+
+                if let Some(i) = index.get(child.name()) {
+                    children[*i] = child;
+                } else {
+                    index.insert(child.name().to_owned(), children.len());
+                    children.push(child);
+                }
+            }
+        }
+        Ok(children)
+    }
+}
+
+#[inline]
+fn is_pack_file(path: &Path) -> bool {
+    path.extension()
+        .map(|x| x.eq_ignore_ascii_case(PackFile::EXTENSION))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::crypto::MapleVersion;
+    use crate::extra::{Entry, EntryCache};
+    use crate::reader::PackLayout;
+    use crate::reader::pack::tests::{TestEntry, temp_dir, write_pack};
+    use crate::reader::wizet::tests::{TestValue, encode_image};
+    use std::path::PathBuf;
+
+    const PLAIN: &[u8] = b"#Property\n";
+
+    /// root of a pack bundle of encoded `images` by path, remove `dir` when done.
+    pub(crate) fn encoded_root<S: AsRef<str>>(
+        tag: &str,
+        images: &[(S, TestValue)],
+    ) -> (PathBuf, EntryCache) {
+        let dir = temp_dir(tag);
+        let data = images
+            .iter()
+            .map(|(_, value)| encode_image(value))
+            .collect::<Vec<_>>();
+        let entries = images
+            .iter()
+            .zip(&data)
+            .map(|((name, _), data)| TestEntry {
+                name: name.as_ref(),
+                data,
+            })
+            .collect::<Vec<_>>();
+        write_pack(
+            &dir.join("Data_000.ms"),
+            2,
+            PackLayout::V2,
+            &[],
+            &entries,
+            0,
+        );
+        let root = Entry::from_packs(&dir, MapleVersion::from(95))
+            .unwrap()
+            .into_cache();
+        (dir, root)
+    }
+
+    #[test]
+    fn later_pack_wins() {
+        let dir = temp_dir("pack-bundle-order");
+        let first = [
+            TestEntry {
+                name: "Folder",
+                data: PLAIN,
+            },
+            TestEntry {
+                name: "Image/a.img",
+                data: PLAIN,
+            },
+            TestEntry {
+                name: "Both/a.img",
+                data: PLAIN,
+            },
+        ];
+        let second = [
+            TestEntry {
+                name: "Folder/b.img",
+                data: PLAIN,
+            },
+            TestEntry {
+                name: "Image",
+                data: PLAIN,
+            },
+            TestEntry {
+                name: "Both/b.img",
+                data: PLAIN,
+            },
+        ];
+        write_pack(&dir.join("Data_000.ms"), 2, PackLayout::V2, &[], &first, 0);
+        write_pack(&dir.join("Data_001.ms"), 2, PackLayout::V2, &[], &second, 0);
+
+        let bundle = PackBundle::from_path(&dir).unwrap().unwrap();
+        let children = bundle.children(&[]).unwrap();
+        let kinds = children
+            .iter()
+            .map(|c| (c.name(), matches!(c, PackChild::Folder(_))))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [("Folder", true), ("Image", false), ("Both", true)]);
+
+        let both = bundle.children(&["Both".to_owned()]).unwrap();
+        let names = both.iter().map(|c| c.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["a.img", "b.img"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,385 @@
+use crate::Error;
+use crate::extra::item::lenient;
+use crate::extra::map::numbered;
+use crate::extra::{EntryCache, StringCategory, StringIndex};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::collections::hash_map;
+
+const IMAGE_EXTENSION: &str = ".img";
+const QUEST_INFO: &str = "Quest/QuestInfo.img";
+const CHECK: &str = "Quest/Check.img";
+const ACT: &str = "Quest/Act.img";
+const SAY: &str = "Quest/Say.img";
+const NPC: &str = "Npc";
+/// phases in `Check.img`, `Act.img` and `Say.img`.
+const START: &str = "0";
+const COMPLETE: &str = "1";
+
+/// numbers and flags of plain text images are strings, `1` and `true` are
+/// both true.
+fn flag(entry: &EntryCache, name: &str) -> Result<bool, Error> {
+    Ok(lenient::<i64>(entry, name)? != 0 || lenient::<bool>(entry, name)?)
+}
+
+/// `Npc/<id>.img` with the texts of `String/Npc.img`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct NpcInfo {
+    pub id: u32,
+    pub name: Option<String>,
+    pub func: Option<String>,
+    /// `info/link`, the npc sprites are shared from.
+    pub link: Option<u32>,
+}
+
+impl NpcInfo {
+    /// `root` holds `Npc`, names are taken from `strings` if given.
+    pub fn try_load(
+        root: &EntryCache,
+        id: u32,
+        strings: Option<&StringIndex>,
+    ) -> Result<NpcInfo, Error> {
+        let mut npc = NpcInfo {
+            id,
+            ..Default::default()
+        };
+        if let Some(info) = root.try_get_by_path(format!("{NPC}/{id:07}{IMAGE_EXTENSION}/info"))? {
+            let link = lenient::<u32>(info, "link")?;
+            npc.link = (link != 0).then_some(link);
+        }
+        if let Some(entry) = strings.and_then(|s| s.get(&StringCategory::Npc, id)) {
+            npc.name = entry.name().map(|s| s.to_owned());
+            npc.func = entry.get("func").map(|s| s.to_owned());
+        }
+        Ok(npc)
+    }
+}
+
+/// item or mob id with count.
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct IdCount {
+    pub id: u32,
+    pub count: i32,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct QuestState {
+    pub id: u32,
+    /// `0` not started, `1` in progress, `2` completed.
+    pub state: i32,
+}
+
+/// `Check.img/<id>/<phase>`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct QuestRequirement {
+    pub npc: Option<u32>,
+    pub level_min: u32,
+    pub level_max: u32,
+    pub fame: i32,
+    pub jobs: Vec<i32>,
+    pub items: Vec<IdCount>,
+    pub mobs: Vec<IdCount>,
+    pub quests: Vec<QuestState>,
+}
+
+impl TryFrom<&EntryCache> for QuestRequirement {
+    type Error = Error;
+
+    fn try_from(check: &EntryCache) -> Result<Self, Self::Error> {
+        let npc = lenient::<u32>(check, "npc")?;
+        let mut requirement = QuestRequirement {
+            npc: (npc != 0).then_some(npc),
+            level_min: lenient(check, "lvmin")?,
+            level_max: lenient(check, "lvmax")?,
+            fame: lenient(check, "pop")?,
+            ..Default::default()
+        };
+        if let Some(jobs) = check.try_get("job")? {
+            for (_, e) in numbered(jobs)? {
+                requirement.jobs.extend(e.try_to::<i32>().ok());
+            }
+        }
+        requirement.items = id_counts(check, "item")?;
+        requirement.mobs = id_counts(check, "mob")?;
+        if let Some(quests) = check.try_get("quest")? {
+            for (_, e) in numbered(quests)? {
+                requirement.quests.push(QuestState {
+                    id: lenient(e, "id")?,
+                    state: lenient(e, "state")?,
+                });
+            }
+        }
+        Ok(requirement)
+    }
+}
+
+fn id_counts(entry: &EntryCache, name: &str) -> Result<Vec<IdCount>, Error> {
+    let mut list = vec![];
+    if let Some(entries) = entry.try_get(name)? {
+        for (_, e) in numbered(entries)? {
+            list.push(IdCount {
+                id: lenient(e, "id")?,
+                count: lenient(e, "count")?,
+            });
+        }
+    }
+    Ok(list)
+}
+
+/// `Act.img/<id>/<phase>/item/<n>`, negative `count` takes the item.
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct RewardItem {
+    pub id: u32,
+    pub count: i32,
+    /// weight of random rewards, `0` for always.
+    pub prop: i32,
+    pub gender: i32,
+    pub job: i32,
+}
+
+/// `Act.img/<id>/<phase>`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct QuestReward {
+    pub exp: i64,
+    pub money: i64,
+    pub fame: i32,
+    pub items: Vec<RewardItem>,
+    pub next_quest: Option<u32>,
+}
+
+impl TryFrom<&EntryCache> for QuestReward {
+    type Error = Error;
+
+    fn try_from(act: &EntryCache) -> Result<Self, Self::Error> {
+        let next = lenient::<u32>(act, "nextQuest")?;
+        let mut reward = QuestReward {
+            exp: lenient(act, "exp")?,
+            money: lenient(act, "money")?,
+            fame: lenient(act, "pop")?,
+            items: vec![],
+            next_quest: (next != 0).then_some(next),
+        };
+        if let Some(items) = act.try_get("item")? {
+            for (_, e) in numbered(items)? {
+                reward.items.push(RewardItem {
+                    id: lenient(e, "id")?,
+                    count: lenient(e, "count")?,
+                    prop: lenient(e, "prop")?,
+                    gender: lenient(e, "gender")?,
+                    job: lenient(e, "job")?,
+                });
+            }
+        }
+        Ok(reward)
+    }
+}
+
+/// start or complete of a quest.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct QuestPhase {
+    /// npc of `requirement.npc`.
+    pub npc: Option<NpcInfo>,
+    pub requirement: QuestRequirement,
+    pub reward: QuestReward,
+    /// numbered lines of `Say.img/<id>/<phase>`.
+    pub dialogue: Vec<String>,
+}
+
+/// `QuestInfo.img/<id>` joined with `Check.img`, `Act.img` and `Say.img`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Quest {
+    pub id: u32,
+    pub name: String,
+    pub parent: String,
+    pub area: i32,
+    pub auto_start: bool,
+    /// numbered summaries of `QuestInfo.img/<id>`.
+    pub summary: Vec<String>,
+    pub start: QuestPhase,
+    pub complete: QuestPhase,
+}
+
+/// every quest of `Quest` in order of id, binary and plain text images are
+/// read the same way.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct QuestTable {
+    pub quests: Vec<Quest>,
+}
+
+impl QuestTable {
+    /// `root` holds `Quest` and `Npc`, npc names are taken from `strings`
+    /// if given.
+    pub fn try_load(root: &EntryCache, strings: Option<&StringIndex>) -> Result<QuestTable, Error> {
+        let info = root.try_get_by_path(QUEST_INFO)?;
+        let check = root.try_get_by_path(CHECK)?;
+        let act = root.try_get_by_path(ACT)?;
+        let say = root.try_get_by_path(SAY)?;
+
+        let mut ids = BTreeSet::new();
+        for image in [info, check, act, say].into_iter().flatten() {
+            ids.extend(
+                image
+                    .try_iter()?
+                    .filter_map(|e| e.name().parse::<u32>().ok()),
+            );
+        }
+
+        let mut npcs = ahash::HashMap::default();
+        let mut quests = Vec::with_capacity(ids.len());
+        for id in ids {
+            let name = id.to_string();
+            let mut quest = Quest {
+                id,
+                ..Default::default()
+            };
+            if let Some(e) = info.map(|i| i.try_get(&name)).transpose()?.flatten() {
+                quest.name = lenient(e, "name")?;
+                quest.parent = lenient(e, "parent")?;
+                quest.area = lenient(e, "area")?;
+                quest.auto_start = flag(e, "autoStart")?;
+                quest.summary = lines(e)?;
+            }
+            for (phase, output) in [(START, &mut quest.start), (COMPLETE, &mut quest.complete)] {
+                let path = format!("{name}/{phase}");
+                if let Some(e) = check
+                    .map(|c| c.try_get_by_path(&path))
+                    .transpose()?
+                    .flatten()
+                {
+                    output.requirement = e.try_to()?;
+                }
+                if let Some(e) = act.map(|a| a.try_get_by_path(&path)).transpose()?.flatten() {
+                    output.reward = e.try_to()?;
+                }
+                if let Some(e) = say.map(|s| s.try_get_by_path(&path)).transpose()?.flatten() {
+                    output.dialogue = lines(e)?;
+                }
+                if let Some(npc) = output.requirement.npc {
+                    let info = match npcs.entry(npc) {
+                        hash_map::Entry::Occupied(e) => e.into_mut(),
+                        hash_map::Entry::Vacant(e) => {
+                            e.insert(NpcInfo::try_load(root, npc, strings)?)
+                        }
+                    };
+                    output.npc = Some(info.clone());
+                }
+            }
+            quests.push(quest);
+        }
+        Ok(QuestTable { quests })
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Unexpected(Box::new(e)))
+    }
+}
+
+/// numbered texts of `entry` in order.
+fn lines(entry: &EntryCache) -> Result<Vec<String>, Error> {
+    Ok(numbered(entry)?
+        .into_iter()
+        .filter(|(_, e)| !e.has_children())
+        .filter_map(|(_, e)| e.try_to::<String>().ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::MapleVersion;
+    use crate::extra::Entry;
+    use crate::reader::PackLayout;
+    use crate::reader::pack::tests::{TestEntry, temp_dir, write_pack};
+
+    #[test]
+    fn plain_images() {
+        let dir = temp_dir("quest-plain");
+        let entries = [
+            TestEntry {
+                name: QUEST_INFO,
+                data: b"#Property\n\
+                    1000 = {\n name = Borrowing the Mirror\n parent = Sera\n area = 20\n\
+                    autoStart = 1\n 0 = go find it\n 1 = bring it back\n}\n\
+                    1001 = {\n name = Second\n autoStart = 0\n}\n",
+            },
+            TestEntry {
+                name: CHECK,
+                data: b"#Property\n\
+                    1000 = {\n 0 = {\n  npc = 2100\n  lvmin = 10\n  lvmax = 30\n\
+                    job = {\n   0 = 0\n   1 = 100\n  }\n\
+                    quest = {\n   0 = {\n    id = 999\n    state = 2\n   }\n  }\n }\n\
+                    1 = {\n  npc = 2100\n  mob = {\n   0 = {\n    id = 100100\n    count = 5\n   }\n  }\n }\n}\n",
+            },
+            TestEntry {
+                name: ACT,
+                data: b"#Property\n\
+                    1000 = {\n 1 = {\n  exp = 200\n  money = 50\n  pop = 1\n  nextQuest = 1001\n\
+                    item = {\n   0 = {\n    id = 4000000\n    count = -5\n   }\n\
+                    1 = {\n    id = 2000000\n    count = 3\n    prop = 10\n   }\n  }\n }\n}\n",
+            },
+            TestEntry {
+                name: SAY,
+                data: b"#Property\n\
+                    1000 = {\n 0 = {\n  0 = Hello\n  1 = Can you help?\n  yes = {\n   0 = Thanks\n  }\n }\n}\n",
+            },
+            TestEntry {
+                name: "Npc/0002100.img",
+                data: b"#Property\ninfo = {\n link = 2101\n}\n",
+            },
+        ];
+        write_pack(
+            &dir.join("Data_000.ms"),
+            2,
+            PackLayout::V2,
+            &[],
+            &entries,
+            0,
+        );
+        let root = Entry::from_packs(&dir, MapleVersion::from(95))
+            .unwrap()
+            .into_cache();
+        let table = QuestTable::try_load(&root, None).unwrap();
+        assert_eq!(table.quests.len(), 2);
+
+        let quest = &table.quests[0];
+        assert_eq!(quest.id, 1000);
+        assert_eq!(quest.name, "Borrowing the Mirror");
+        assert_eq!((quest.parent.as_str(), quest.area), ("Sera", 20));
+        assert!(quest.auto_start);
+        assert!(!table.quests[1].auto_start);
+        assert_eq!(quest.summary, ["go find it", "bring it back"]);
+
+        let start = &quest.start.requirement;
+        assert_eq!(start.npc, Some(2100));
+        assert_eq!((start.level_min, start.level_max), (10, 30));
+        assert_eq!(start.jobs, [0, 100]);
+        assert_eq!((start.quests[0].id, start.quests[0].state), (999, 2));
+        let npc = quest.start.npc.as_ref().unwrap();
+        assert_eq!((npc.id, npc.link), (2100, Some(2101)));
+        let mob = quest.complete.requirement.mobs[0];
+        assert_eq!((mob.id, mob.count), (100100, 5));
+
+        let reward = &quest.complete.reward;
+        assert_eq!((reward.exp, reward.money, reward.fame), (200, 50, 1));
+        assert_eq!(reward.next_quest, Some(1001));
+        assert_eq!((reward.items[0].id, reward.items[0].count), (4000000, -5));
+        assert_eq!((reward.items[1].count, reward.items[1].prop), (3, 10));
+        assert_eq!(quest.start.reward.exp, 0);
+
+        // nested branches of the dialogue are not lines
+        assert_eq!(quest.start.dialogue, ["Hello", "Can you help?"]);
+        assert!(quest.complete.dialogue.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
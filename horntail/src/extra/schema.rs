@@ -0,0 +1,491 @@
+use crate::Error;
+use crate::extra::{Entry, EntryPrimitive, EntryValue};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// numbered children like frames are folded into this segment.
+const NUMBERED: &str = "*";
+const MAX_EXAMPLES: usize = 3;
+const MAX_EXAMPLE_LEN: usize = 64;
+/// strict and reserved keywords of all editions, escaped by `r#`.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+/// keywords can't be raw identifiers, suffixed by `_`.
+const PATH_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// observations of a path relative to the images.
+#[derive(Clone, Debug, Default)]
+pub struct PathSchema {
+    /// `Primitive` variant or `EntryKind` name to count.
+    pub kinds: BTreeMap<String, usize>,
+    /// count of images have this path.
+    pub present: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub examples: Vec<String>,
+}
+
+impl PathSchema {
+    /// the kind observed most.
+    pub fn kind(&self) -> Option<&str> {
+        self.kinds
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(kind, _)| kind.as_str())
+    }
+
+    fn observe(&mut self, value: &EntryValue) {
+        let kind = match value {
+            EntryValue::Kind(kind) => kind.as_str().to_owned(),
+            EntryValue::Primitive(primitive) => {
+                let (kind, number) = match primitive {
+                    EntryPrimitive::Nil => ("Nil", None),
+                    EntryPrimitive::Int16(i) => ("Int16", Some(*i as f64)),
+                    EntryPrimitive::Int32(i) => ("Int32", Some(*i as f64)),
+                    EntryPrimitive::Int64(i) => ("Int64", Some(*i as f64)),
+                    EntryPrimitive::Float32(f) => ("Float32", Some(*f as f64)),
+                    EntryPrimitive::Float64(f) => ("Float64", Some(*f)),
+                    EntryPrimitive::String(_) => ("String", None),
+                };
+                if let Some(n) = number {
+                    self.min = Some(self.min.map_or(n, |min| min.min(n)));
+                    self.max = Some(self.max.map_or(n, |max| max.max(n)));
+                }
+                let example = match primitive {
+                    EntryPrimitive::Nil => None,
+                    EntryPrimitive::Int16(i) => Some(i.to_string()),
+                    EntryPrimitive::Int32(i) => Some(i.to_string()),
+                    EntryPrimitive::Int64(i) => Some(i.to_string()),
+                    EntryPrimitive::Float32(f) => Some(f.to_string()),
+                    EntryPrimitive::Float64(f) => Some(f.to_string()),
+                    EntryPrimitive::String(s) => Some(s.chars().take(MAX_EXAMPLE_LEN).collect()),
+                };
+                if let Some(example) = example
+                    && self.examples.len() < MAX_EXAMPLES
+                    && !self.examples.contains(&example)
+                {
+                    self.examples.push(example);
+                }
+                kind.to_owned()
+            }
+        };
+        *self.kinds.entry(kind).or_default() += 1;
+    }
+}
+
+/// layout inferred from images, paths are relative to the images and
+/// numbered segments are folded into `*`.
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    /// count of images observed.
+    pub images: usize,
+    pub paths: BTreeMap<String, PathSchema>,
+}
+
+impl Schema {
+    /// infer from entries of `root` matching `pattern` like `Mob/*.img`, `*`
+    /// and `?` match within a path segment.
+    pub fn try_infer(root: &Entry, pattern: &str) -> Result<Schema, Error> {
+        let segments = pattern
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let mut schema = Schema::default();
+        schema.walk_pattern(root, &segments)?;
+        Ok(schema)
+    }
+
+    fn walk_pattern(&mut self, entry: &Entry, segments: &[&str]) -> Result<(), Error> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.try_add(entry);
+        };
+        for child in entry.try_iter()? {
+            if glob_match(segment, child.name()) {
+                self.walk_pattern(&child, rest)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// observe one more image.
+    pub fn try_add(&mut self, image: &Entry) -> Result<(), Error> {
+        let mut seen = BTreeSet::new();
+        self.walk(image, "", &mut seen)?;
+        for path in seen {
+            if let Some(schema) = self.paths.get_mut(&path) {
+                schema.present += 1;
+            }
+        }
+        self.images += 1;
+        Ok(())
+    }
+
+    fn walk(
+        &mut self,
+        entry: &Entry,
+        prefix: &str,
+        seen: &mut BTreeSet<String>,
+    ) -> Result<(), Error> {
+        for child in entry.try_iter()? {
+            let segment = if child.name().parse::<u64>().is_ok() {
+                NUMBERED
+            } else {
+                child.name()
+            };
+            let path = if prefix.is_empty() {
+                segment.to_owned()
+            } else {
+                format!("{prefix}/{segment}")
+            };
+            self.paths
+                .entry(path.clone())
+                .or_default()
+                .observe(child.value());
+            if child.has_children() {
+                self.walk(&child, &path, seen)?;
+            }
+            seen.insert(path);
+        }
+        Ok(())
+    }
+
+    fn tree(&self) -> Node<'_> {
+        let mut root = Node::default();
+        for (path, schema) in self.paths.iter() {
+            let mut node = &mut root;
+            for segment in path.split('/') {
+                node = node.children.entry(segment).or_default();
+            }
+            node.schema = Some(schema);
+        }
+        root
+    }
+
+    /// Rust struct skeleton, root struct is named `name`. paths not in every
+    /// parent are `Option`, numbered children are `Vec`.
+    pub fn to_rust(&self, name: &str) -> String {
+        let mut out = String::new();
+        let mut pending = vec![(camel_case(name), self.tree(), self.images)];
+        while let Some((name, node, present)) = pending.pop() {
+            let _ = writeln!(out, "pub struct {name} {{");
+            if let Some(kind) = node.schema.and_then(|s| s.kind())
+                && let Some(typ) = rust_type(kind)
+            {
+                let _ = writeln!(out, "    pub value: {typ},");
+            }
+            for (segment, child) in node.children {
+                let schema = child.schema.cloned().unwrap_or_default();
+                let kind = schema.kind().unwrap_or_default();
+                let mut typ = if child.children.is_empty() {
+                    rust_type(kind).unwrap_or("()").to_owned()
+                } else {
+                    let child_name = format!("{name}{}", camel_case(segment));
+                    pending.push((child_name.clone(), child, schema.present));
+                    child_name
+                };
+                let field = if segment == NUMBERED {
+                    typ = format!("Vec<{typ}>");
+                    "items".to_owned()
+                } else {
+                    snake_case(segment)
+                };
+                if segment != NUMBERED && schema.present < present {
+                    typ = format!("Option<{typ}>");
+                }
+                let _ = writeln!(
+                    out,
+                    "    /// `{segment}` present {}/{present}{}",
+                    schema.present,
+                    describe(&schema)
+                );
+                let _ = writeln!(out, "    pub {field}: {typ},");
+            }
+            let _ = writeln!(out, "}}\n");
+        }
+        out.truncate(out.trim_end().len() + 1);
+        out
+    }
+
+    /// JSON Schema of images, paths present in every parent are `required`.
+    #[cfg(feature = "json")]
+    pub fn to_json_schema(&self) -> Result<String, Error> {
+        use serde_json::{Map, Value, json};
+
+        fn node_schema(node: Node, present: usize) -> Value {
+            let mut value = Map::new();
+            if let Some(schema) = node.schema {
+                let types = schema
+                    .kinds
+                    .keys()
+                    .filter_map(|k| json_type(k))
+                    .collect::<BTreeSet<_>>();
+                if !node.children.is_empty() || types.len() > 1 {
+                    let kinds = schema
+                        .kinds
+                        .iter()
+                        .map(|(kind, count)| format!("{kind} ({count})"))
+                        .collect::<Vec<_>>();
+                    value.insert("description".into(), json!(kinds.join(", ")));
+                }
+                if node.children.is_empty() {
+                    match types.len() {
+                        0 => {}
+                        1 => {
+                            value.insert("type".into(), json!(types.first()));
+                        }
+                        _ => {
+                            value.insert("type".into(), json!(types));
+                        }
+                    }
+                }
+                if let Some(min) = schema.min {
+                    value.insert("minimum".into(), json!(min));
+                }
+                if let Some(max) = schema.max {
+                    value.insert("maximum".into(), json!(max));
+                }
+                if !schema.examples.is_empty() {
+                    value.insert("examples".into(), json!(schema.examples));
+                }
+            }
+            if !node.children.is_empty() {
+                value.insert("type".into(), json!("object"));
+                let mut properties = Map::new();
+                let mut required = vec![];
+                let mut items = None;
+                for (segment, child) in node.children {
+                    let child_present = child.schema.map(|s| s.present).unwrap_or_default();
+                    let schema = node_schema(child, child_present);
+                    if segment == NUMBERED {
+                        items = Some(schema);
+                        continue;
+                    }
+                    if child_present >= present {
+                        required.push(segment);
+                    }
+                    properties.insert(segment.to_owned(), schema);
+                }
+                if !properties.is_empty() {
+                    value.insert("properties".into(), Value::Object(properties));
+                }
+                if !required.is_empty() {
+                    value.insert("required".into(), json!(required));
+                }
+                if let Some(items) = items {
+                    value.insert("additionalProperties".into(), items);
+                }
+            }
+            Value::Object(value)
+        }
+
+        let mut schema = node_schema(self.tree(), self.images);
+        if let Value::Object(map) = &mut schema {
+            map.insert(
+                "$schema".into(),
+                json!("https://json-schema.org/draft/2020-12/schema"),
+            );
+        }
+        serde_json::to_string_pretty(&schema).map_err(|e| Error::Unexpected(Box::new(e)))
+    }
+}
+
+#[derive(Default)]
+struct Node<'a> {
+    schema: Option<&'a PathSchema>,
+    children: BTreeMap<&'a str, Node<'a>>,
+}
+
+fn describe(schema: &PathSchema) -> String {
+    let mut s = String::new();
+    if schema.kinds.len() > 1 {
+        let kinds = schema.kinds.keys().cloned().collect::<Vec<_>>();
+        let _ = write!(s, ", kinds {}", kinds.join("|"));
+    }
+    if let (Some(min), Some(max)) = (schema.min, schema.max) {
+        let _ = write!(s, ", range {min}..={max}");
+    }
+    if !schema.examples.is_empty() {
+        let _ = write!(s, ", e.g. {:?}", schema.examples);
+    }
+    s
+}
+
+fn rust_type(kind: &str) -> Option<&'static str> {
+    Some(match kind {
+        "Int16" => "i16",
+        "Int32" => "i32",
+        "Int64" => "i64",
+        "Float32" => "f32",
+        "Float64" => "f64",
+        "String" => "String",
+        "Canvas" | "Canvas#Video" => "Canvas",
+        "Shape2D#Vector2D" => "Vector2D",
+        "Shape2D#Convex2D" => "Convex2D",
+        "UOL" => "UOL",
+        "Sound_DX8" => "Sound",
+        "RawData" => "RawData",
+        "Script" => "Script",
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "json")]
+fn json_type(kind: &str) -> Option<&'static str> {
+    Some(match kind {
+        "Nil" => "null",
+        "Int16" | "Int32" | "Int64" => "integer",
+        "Float32" | "Float64" => "number",
+        "String" | "UOL" => "string",
+        "Unknown" => return None,
+        _ => "object",
+    })
+}
+
+fn camel_case(s: &str) -> String {
+    let mut out = String::new();
+    let mut upper = true;
+    for c in s.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = true;
+            continue;
+        }
+        if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    if s == NUMBERED {
+        out.push_str("Item");
+    }
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    escape_keyword(out)
+}
+
+fn snake_case(s: &str) -> String {
+    let chars = s.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            // word starts like `maxHP` and `PADamage`
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1);
+            let boundary = prev.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(|p| p.is_ascii_uppercase())
+                    && next.is_some_and(|n| n.is_ascii_lowercase()));
+            if boundary && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            out.push(*c);
+        } else if !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    escape_keyword(out)
+}
+
+fn escape_keyword(mut ident: String) -> String {
+    if PATH_KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        ident.insert_str(0, "r#");
+    }
+    ident
+}
+
+/// `*` matches any characters and `?` matches one character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n) = (pattern.as_bytes(), name.as_bytes());
+    let (mut pi, mut ni) = (0, 0);
+    let mut star = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == b'?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == b'*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(kind: &str, present: usize) -> PathSchema {
+        PathSchema {
+            kinds: BTreeMap::from([(kind.to_owned(), present)]),
+            present,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn snake_case_names() {
+        assert_eq!(snake_case("maxHP"), "max_hp");
+        assert_eq!(snake_case("PADamage"), "pa_damage");
+        assert_eq!(snake_case("mob.skill"), "mob_skill");
+        assert_eq!(snake_case("1st"), "_1st");
+        assert_eq!(snake_case("type"), "r#type");
+        assert_eq!(snake_case("yield"), "r#yield");
+        assert_eq!(snake_case("Self"), "self_");
+        assert_eq!(snake_case("super"), "super_");
+        assert_eq!(camel_case("self"), "Self_");
+    }
+
+    #[test]
+    fn rust_struct() {
+        let schema = Schema {
+            images: 2,
+            paths: BTreeMap::from([
+                ("type".to_owned(), path("Int32", 2)),
+                ("async".to_owned(), path("String", 1)),
+                ("self".to_owned(), path("Float64", 2)),
+                ("crate".to_owned(), path("Folder", 2)),
+                ("crate/dyn".to_owned(), path("Int16", 2)),
+                ("crate/*".to_owned(), path("Canvas", 2)),
+            ]),
+        };
+        let rust = schema.to_rust("mob");
+        let fields = rust
+            .lines()
+            .filter(|l| l.trim_start().starts_with("pub ") || l.starts_with('}'))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                "pub struct Mob {",
+                "    pub r#async: Option<String>,",
+                "    pub crate_: MobCrate,",
+                "    pub self_: f64,",
+                "    pub r#type: i32,",
+                "}",
+                "pub struct MobCrate {",
+                "    pub items: Vec<Canvas>,",
+                "    pub r#dyn: i16,",
+                "}",
+            ]
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::Error;
+use crate::extra::map::numbered;
+use crate::extra::{EntryCache, EntryPrimitive, EntryValue};
+use std::iter::Peekable;
+use std::str::Chars;
+
+const COMMON: &str = "common";
+const LEVEL: &str = "level";
+const MAX_LEVEL: &str = "maxLevel";
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Function {
+    /// ceil
+    U,
+    /// floor
+    D,
+    /// natural logarithm
+    Log,
+    Log10,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "u" => Some(Function::U),
+            "d" => Some(Function::D),
+            "log" => Some(Function::Log),
+            "log10" => Some(Function::Log10),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn apply(&self, v: f64) -> f64 {
+        match self {
+            Function::U => v.ceil(),
+            Function::D => v.floor(),
+            Function::Log => v.ln(),
+            Function::Log10 => v.log10(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(f64),
+    X,
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, x: f64) -> f64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::X => x,
+            Expr::Neg(e) => -e.eval(x),
+            Expr::Binary(op, l, r) => {
+                let (l, r) = (l.eval(x), r.eval(x));
+                match op {
+                    '+' => l + r,
+                    '-' => l - r,
+                    '*' => l * r,
+                    '/' => l / r,
+                    _ => l % r,
+                }
+            }
+            Expr::Call(f, e) => f.apply(e.eval(x)),
+        }
+    }
+}
+
+/// recursive descent parser of
+///
+/// ```text
+/// expr   = term (('+' | '-') term)*
+/// term   = unary (('*' | '/' | '%') unary)*
+/// unary  = '-' unary | '+' unary | atom
+/// atom   = number | 'x' | name '(' expr ')' | '(' expr ')'
+/// ```
+struct Parser<'a> {
+    source: &'a str,
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn error(&self) -> Error {
+        Error::UnexpectedData(format!("invalid formula `{}`", self.source))
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        match self.peek() {
+            Some(n) if n == c => {
+                self.chars.next();
+                Ok(())
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let mut left = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.chars.next();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, Error> {
+        let mut left = self.unary()?;
+        while let Some(op @ ('*' | '/' | '%')) = self.peek() {
+            self.chars.next();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        match self.peek() {
+            Some('-') => {
+                self.chars.next();
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some('+') => {
+                self.chars.next();
+                self.unary()
+            }
+            _ => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, Error> {
+        match self.peek() {
+            Some('(') => {
+                self.chars.next();
+                let e = self.expr()?;
+                self.expect(')')?;
+                Ok(e)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                number.parse().map(Expr::Number).map_err(|_| self.error())
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let mut name = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric()) {
+                    name.push(c.to_ascii_lowercase());
+                }
+                if name == "x" {
+                    return Ok(Expr::X);
+                }
+                let function = Function::from_name(&name).ok_or_else(|| self.error())?;
+                self.expect('(')?;
+                let e = self.expr()?;
+                self.expect(')')?;
+                Ok(Expr::Call(function, Box::new(e)))
+            }
+            _ => Err(self.error()),
+        }
+    }
+}
+
+/// skill level formula like `10+d(x/2)`, `x` is the skill level, `u` is
+/// ceil and `d` is floor.
+#[derive(Clone, Debug)]
+pub struct Formula {
+    expr: Expr,
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Formula, Error> {
+        let mut parser = Parser {
+            source,
+            chars: source.chars().peekable(),
+        };
+        let expr = parser.expr()?;
+        if parser.peek().is_some() {
+            return Err(parser.error());
+        }
+        Ok(Formula { expr })
+    }
+
+    #[inline]
+    pub fn eval(&self, x: f64) -> f64 {
+        self.expr.eval(x)
+    }
+
+    /// value at `level` truncated like the client does.
+    #[inline]
+    pub fn eval_level(&self, level: u32) -> i64 {
+        self.eval(level as f64) as i64
+    }
+}
+
+/// per level stats of `Skill/<job>.img/skill/<id>`, materialized from
+/// `common` formulas or read from `level/<n>` of old skills.
+#[derive(Clone, Debug, Default)]
+pub struct SkillLevels {
+    pub max_level: u32,
+    /// stats of level 1 at index 0, non numeric properties are left out.
+    pub levels: Vec<ahash::HashMap<String, i64>>,
+}
+
+impl SkillLevels {
+    #[inline]
+    pub fn level(&self, level: u32) -> Option<&ahash::HashMap<String, i64>> {
+        self.levels.get((level as usize).checked_sub(1)?)
+    }
+
+    #[inline]
+    pub fn stat(&self, level: u32, name: &str) -> Option<i64> {
+        self.level(level)?.get(name).copied()
+    }
+}
+
+impl TryFrom<&EntryCache> for SkillLevels {
+    type Error = Error;
+
+    fn try_from(skill: &EntryCache) -> Result<Self, Self::Error> {
+        if let Some(common) = skill.try_get(COMMON)? {
+            let max_level = common.try_get_default::<u32>(MAX_LEVEL)?;
+            let mut formulas = vec![];
+            for e in common.try_iter()? {
+                if e.name() == MAX_LEVEL {
+                    continue;
+                }
+                let formula = match e.value() {
+                    // texts other than formulas are left out
+                    EntryValue::Primitive(EntryPrimitive::String(s)) => match Formula::parse(s) {
+                        Ok(formula) => formula,
+                        Err(_) => continue,
+                    },
+                    EntryValue::Primitive(EntryPrimitive::Nil) | EntryValue::Kind(_) => continue,
+                    EntryValue::Primitive(_) => Formula {
+                        expr: Expr::Number(e.try_to::<f64>()?),
+                    },
+                };
+                formulas.push((e.name().to_owned(), formula));
+            }
+            let levels = (1..=max_level)
+                .map(|level| {
+                    formulas
+                        .iter()
+                        .map(|(name, f)| (name.clone(), f.eval_level(level)))
+                        .collect()
+                })
+                .collect();
+            return Ok(SkillLevels { max_level, levels });
+        }
+
+        let mut levels = vec![];
+        if let Some(level) = skill.try_get(LEVEL)? {
+            for (_, e) in numbered(level)? {
+                let mut stats = ahash::HashMap::default();
+                for stat in e.try_iter()? {
+                    match stat.value() {
+                        EntryValue::Primitive(EntryPrimitive::Nil) | EntryValue::Kind(_) => {}
+                        EntryValue::Primitive(_) => {
+                            if let Ok(v) = stat.try_to::<i64>() {
+                                stats.insert(stat.name().to_owned(), v);
+                            }
+                        }
+                    }
+                }
+                levels.push(stats);
+            }
+        }
+        Ok(SkillLevels {
+            max_level: levels.len() as u32,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, x: u32) -> i64 {
+        Formula::parse(source).unwrap().eval_level(x)
+    }
+
+    #[test]
+    fn formula_precedence() {
+        assert_eq!(eval("1+2*3", 0), 7);
+        assert_eq!(eval("(1+2)*3", 0), 9);
+        assert_eq!(eval("10-4-3", 0), 3);
+        assert_eq!(eval("17%5*2", 0), 4);
+        assert_eq!(eval("-x+30", 10), 20);
+        assert_eq!(eval("- -x", 4), 4);
+        assert_eq!(eval(" 2 * x + 0.5 ", 3), 6);
+    }
+
+    #[test]
+    fn formula_functions() {
+        assert_eq!(eval("10+d(x/3)", 5), 11);
+        assert_eq!(eval("10+u(x/3)", 5), 12);
+        assert_eq!(eval("D(x/2)+U(x/2)", 7), 7);
+        assert_eq!(eval("d(log10(x))", 1000), 3);
+        assert_eq!(eval("u(log(x))", 3), 2);
+    }
+
+    #[test]
+    fn formula_truncates_toward_zero() {
+        assert_eq!(eval("x/3", 5), 1);
+        assert_eq!(eval("-x/3", 5), -1);
+        assert_eq!(Formula::parse("x/4").unwrap().eval(2.0), 0.5);
+    }
+
+    #[test]
+    fn formula_errors() {
+        for source in [
+            "", "1+", "(x", "x)", "2 x", "foo(x)", "d x", "d(x", "1..2", "*3", "x$1",
+        ] {
+            match Formula::parse(source) {
+                Err(Error::UnexpectedData(message)) => {
+                    assert_eq!(message, format!("invalid formula `{source}`"))
+                }
+                _ => panic!("`{source}` should not parse"),
+            }
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use crate::Error;
+use crate::extra::{EntryCache, EntryValue};
+use std::fmt::{Display, Formatter};
+
+const IMAGE_EXTENSION: &str = ".img";
+/// old versions keep all items in one image with a folder per category.
+const ITEM_IMAGE: &str = "Item";
+
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum StringCategory {
+    Consume,
+    Install,
+    Etc,
+    Cash,
+    Pet,
+    Eqp,
+    Mob,
+    Npc,
+    Skill,
+    Map,
+    Quest,
+    /// images or folders not known, by name.
+    Other(String),
+}
+
+impl StringCategory {
+    fn from_name(name: &str) -> StringCategory {
+        match name {
+            "Consume" | "Con" => StringCategory::Consume,
+            "Ins" | "Install" => StringCategory::Install,
+            "Etc" => StringCategory::Etc,
+            "Cash" => StringCategory::Cash,
+            "Pet" => StringCategory::Pet,
+            "Eqp" => StringCategory::Eqp,
+            "Mob" => StringCategory::Mob,
+            "Npc" => StringCategory::Npc,
+            "Skill" => StringCategory::Skill,
+            "Map" => StringCategory::Map,
+            "Quest" | "QuestInfo" => StringCategory::Quest,
+            name => StringCategory::Other(name.to_owned()),
+        }
+    }
+}
+
+impl Display for StringCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StringCategory::Other(name) => f.write_str(name),
+            category => write!(f, "{category:?}"),
+        }
+    }
+}
+
+/// text fields of an id, like `name` and `desc`.
+#[derive(Clone, Debug, Default)]
+pub struct StringEntry {
+    pub fields: ahash::HashMap<String, String>,
+}
+
+impl StringEntry {
+    #[inline]
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(|s| s.as_str())
+    }
+
+    /// `name`, or `mapName` of maps.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.get("name").or_else(|| self.get("mapName"))
+    }
+
+    #[inline]
+    pub fn desc(&self) -> Option<&str> {
+        self.get("desc")
+    }
+}
+
+/// `(category, id)` to texts from the `String` bundle, records are found by
+/// walking down to numbered entries holds texts, so layouts like
+/// `Eqp.img/Eqp/<category>/<id>`, `Map.img/<region>/<id>` and the old
+/// `Item.img/<category>/<id>` are handled the same way.
+#[derive(Default)]
+pub struct StringIndex {
+    entries: ahash::HashMap<StringCategory, ahash::HashMap<u32, StringEntry>>,
+}
+
+impl StringIndex {
+    #[inline]
+    pub fn get(&self, category: &StringCategory, id: u32) -> Option<&StringEntry> {
+        self.entries.get(category)?.get(&id)
+    }
+
+    #[inline]
+    pub fn name(&self, category: &StringCategory, id: u32) -> Option<&str> {
+        self.get(category, id)?.name()
+    }
+
+    #[inline]
+    pub fn desc(&self, category: &StringCategory, id: u32) -> Option<&str> {
+        self.get(category, id)?.desc()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.values().map(|ids| ids.len()).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.values().all(|ids| ids.is_empty())
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&StringCategory, u32, &StringEntry)> {
+        self.entries
+            .iter()
+            .flat_map(|(c, ids)| ids.iter().map(move |(id, e)| (c, *id, e)))
+    }
+
+    /// ids of `category` in order.
+    pub fn ids(&self, category: &StringCategory) -> Vec<u32> {
+        let mut ids = self
+            .entries
+            .get(category)
+            .map(|ids| ids.keys().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// names matching `query` case insensitively, best first. exact matches
+    /// go before prefixes, substrings and then subsequences.
+    pub fn search(&self, query: &str) -> Vec<(&StringCategory, u32, &StringEntry)> {
+        let query = query.to_lowercase();
+        let mut found = self
+            .iter()
+            .filter_map(|(category, id, entry)| {
+                let score = fuzzy_score(&entry.name()?.to_lowercase(), &query)?;
+                Some((score, category, id, entry))
+            })
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| (a.0, a.1, a.2).cmp(&(b.0, b.1, b.2)));
+        found
+            .into_iter()
+            .map(|(_, category, id, entry)| (category, id, entry))
+            .collect()
+    }
+
+    fn collect(
+        &mut self,
+        category: &StringCategory,
+        entry: &EntryCache,
+        depth: usize,
+    ) -> Result<(), Error> {
+        if depth > 0
+            && let Ok(id) = entry.name().parse::<u32>()
+        {
+            let mut record = StringEntry::default();
+            for field in entry.try_iter()? {
+                if let EntryValue::Primitive(_) = field.value()
+                    && let Ok(text) = field.try_to::<String>()
+                {
+                    record.fields.insert(field.name().to_owned(), text);
+                }
+            }
+            if !record.fields.is_empty() {
+                self.entries
+                    .entry(category.clone())
+                    .or_default()
+                    .insert(id, record);
+                return Ok(());
+            }
+        }
+        for child in entry.try_iter()? {
+            if child.has_children() {
+                self.collect(category, child, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&EntryCache> for StringIndex {
+    type Error = Error;
+
+    /// `root` is the `String` bundle root.
+    fn try_from(root: &EntryCache) -> Result<Self, Self::Error> {
+        let mut index = StringIndex::default();
+        for image in root.try_iter()? {
+            let name = image.name().trim_end_matches(IMAGE_EXTENSION);
+            if name == ITEM_IMAGE {
+                for folder in image.try_iter()? {
+                    let category = StringCategory::from_name(folder.name());
+                    index.collect(&category, folder, 1)?;
+                }
+            } else {
+                index.collect(&StringCategory::from_name(name), image, 0)?;
+            }
+        }
+        Ok(index)
+    }
+}
+
+/// how a name matches a query, better first.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum MatchTier {
+    Exact,
+    Prefix,
+    Substring,
+    Subsequence,
+}
+
+/// tier then score within the tier, lower is better, `None` for not matched.
+fn fuzzy_score(name: &str, query: &str) -> Option<(MatchTier, usize)> {
+    if query.is_empty() {
+        return None;
+    }
+    if name == query {
+        return Some((MatchTier::Exact, 0));
+    }
+    if let Some(pos) = name.find(query) {
+        return Some(if pos == 0 {
+            // shorter names are closer
+            (MatchTier::Prefix, name.len() - query.len())
+        } else {
+            (MatchTier::Substring, pos)
+        });
+    }
+    // subsequence, scored by the gaps between matched characters
+    let mut chars = name.char_indices();
+    let mut gaps = 0;
+    let mut last = None;
+    for q in query.chars() {
+        let (i, _) = chars.find(|(_, c)| *c == q)?;
+        if let Some(last) = last {
+            gaps += i - last - 1;
+        }
+        last = Some(i);
+    }
+    Some((MatchTier::Subsequence, gaps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_tiers() {
+        assert_eq!(fuzzy_score("snail", "snail"), Some((MatchTier::Exact, 0)));
+        assert_eq!(
+            fuzzy_score("snail shell", "snail"),
+            Some((MatchTier::Prefix, 6))
+        );
+        assert_eq!(
+            fuzzy_score("red snail", "snail"),
+            Some((MatchTier::Substring, 4))
+        );
+        assert_eq!(
+            fuzzy_score("s-n-a-i-l", "snail"),
+            Some((MatchTier::Subsequence, 4))
+        );
+        assert_eq!(fuzzy_score("nails", "snail"), None);
+        assert_eq!(fuzzy_score("snail", ""), None);
+    }
+
+    #[test]
+    fn fuzzy_score_ordering() {
+        // a long prefix match still goes before a short substring one
+        let mut names = vec![
+            "sn ail",
+            "a snail",
+            "snail with a very long name after it",
+            "snail",
+            "snails",
+            "giant snail",
+        ];
+        names.sort_by_key(|name| fuzzy_score(name, "snail").unwrap());
+        assert_eq!(
+            names,
+            [
+                "snail",
+                "snails",
+                "snail with a very long name after it",
+                "a snail",
+                "giant snail",
+                "sn ail",
+            ]
+        );
+    }
+
+    #[test]
+    fn search_by_tier() {
+        let mut index = StringIndex::default();
+        for (category, id, name) in [
+            (StringCategory::Mob, 100100, "Snail"),
+            (StringCategory::Mob, 100101, "Blue Snail"),
+            (StringCategory::Etc, 4000000, "Snail Shell"),
+            (StringCategory::Npc, 1000, "Sun Nail"),
+        ] {
+            let mut entry = StringEntry::default();
+            entry.fields.insert("name".to_owned(), name.to_owned());
+            index.entries.entry(category).or_default().insert(id, entry);
+        }
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.name(&StringCategory::Mob, 100101), Some("Blue Snail"));
+        assert_eq!(index.ids(&StringCategory::Mob), [100100, 100101]);
+
+        let found = index
+            .search("SNAIL")
+            .into_iter()
+            .map(|(_, id, _)| id)
+            .collect::<Vec<_>>();
+        assert_eq!(found, [100100, 4000000, 100101, 1000]);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::crypto::MapleCipher;
 use crate::error::Error;
+use crate::reader::StringDecoder;
 use std::io::SeekFrom;
 
 macro_rules! buf_try_get_impl {
@@ -38,6 +39,12 @@ pub trait Accessor: MapleCipher {
     fn try_seek(&mut self, style: SeekFrom) -> Result<u64, Error>;
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error>;
 
+    /// decoder of single-byte strings
+    #[inline]
+    fn string_decoder(&self) -> StringDecoder {
+        StringDecoder::default()
+    }
+
     #[inline]
     fn seek(&mut self, style: SeekFrom) {
         self.try_seek(style).unwrap_or_else(|e| panic!("seek: {e}"));
@@ -227,11 +234,7 @@ pub trait Accessor: MapleCipher {
                 self.crypt(&mut chunk);
 
                 // nx does put 0xd7 latin1 char in to bytes that is so grouse
-                let (str, _, had_error) = encoding_rs::WINDOWS_1252.decode(&chunk);
-                if had_error {
-                    return Err(Error::InvalidCharacter);
-                }
-                Ok(str.into_owned())
+                self.string_decoder().decode(&chunk)
             }
             VarKind::Positive(_) => {
                 chunk.chunks_exact_mut(2).enumerate().for_each(|(i, b)| {
@@ -1,5 +1,5 @@
 use crate::crypto::MapleCipher;
-use crate::reader::Accessor;
+use crate::reader::{Accessor, StringDecoder};
 use crate::{AccessorBuilder, Error};
 use std::io;
 use std::io::{ErrorKind, Read, SeekFrom};
@@ -10,6 +10,7 @@ pub struct BinaryAccessor<T> {
     data: T,
     pos: u64,
     size: u64,
+    decoder: StringDecoder,
 }
 
 impl<T: AsRef<[u8]>> BinaryAccessor<T> {
@@ -25,6 +26,31 @@ impl<T: AsRef<[u8]>> BinaryAccessor<T> {
             data,
             pos: 0,
             size,
+            decoder: StringDecoder::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_decoder(mut self, decoder: StringDecoder) -> BinaryAccessor<T> {
+        self.decoder = decoder;
+        self
+    }
+}
+
+impl<'a> BinaryAccessor<&'a [u8]> {
+    /// borrow next `n` bytes from the underlying slice without decryption.
+    pub fn try_borrow(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        let data: &'a [u8] = self.data;
+        let start = self.pos as usize;
+        match start.checked_add(n).and_then(|end| data.get(start..end)) {
+            Some(slice) => {
+                self.pos += n as u64;
+                Ok(slice)
+            }
+            None => Err(Error::IO(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "borrow beyond the end of data",
+            ))),
         }
     }
 }
@@ -90,6 +116,11 @@ impl<T: AsRef<[u8]>> Accessor for BinaryAccessor<T> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
         Ok(Read::read(self, buf)?)
     }
+
+    #[inline]
+    fn string_decoder(&self) -> StringDecoder {
+        self.decoder
+    }
 }
 
 #[derive(Clone)]
@@ -104,6 +135,7 @@ impl AsRef<[u8]> for RcSliceWrapper {
 pub struct BinaryBuilder {
     cipher: Box<dyn MapleCipher>,
     slice: RcSliceWrapper,
+    decoder: StringDecoder,
 }
 
 impl BinaryBuilder {
@@ -122,8 +154,15 @@ impl BinaryBuilder {
         BinaryBuilder {
             cipher,
             slice: RcSliceWrapper(Rc::new(source)),
+            decoder: StringDecoder::default(),
         }
     }
+
+    #[inline]
+    pub fn with_decoder(mut self, decoder: StringDecoder) -> BinaryBuilder {
+        self.decoder = decoder;
+        self
+    }
 }
 
 impl AccessorBuilder for BinaryBuilder {
@@ -131,13 +170,21 @@ impl AccessorBuilder for BinaryBuilder {
         Box::new(BinaryBuilder {
             cipher: self.cipher.clone_boxed(),
             slice: self.slice.clone(),
+            decoder: self.decoder,
         })
     }
 
     fn accessor(&self) -> Box<dyn Accessor> {
-        Box::new(BinaryAccessor::from_boxed(
-            self.cipher.clone_boxed(),
-            self.slice.clone(),
-        ))
+        Box::new(
+            BinaryAccessor::from_boxed(self.cipher.clone_boxed(), self.slice.clone())
+                .with_decoder(self.decoder),
+        )
+    }
+
+    fn binary(&self) -> Option<BinaryAccessor<&[u8]>> {
+        Some(
+            BinaryAccessor::from_boxed(self.cipher.clone_boxed(), self.slice.as_ref())
+                .with_decoder(self.decoder),
+        )
     }
 }
@@ -0,0 +1,168 @@
+use crate::error::Error;
+use encoding_rs::Encoding;
+
+/// code page of single-byte strings, old regional clients store their native
+/// multi-byte encoding in those strings.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum CodePage {
+    #[default]
+    Windows1252,
+    /// KMS, CP949
+    EucKr,
+    /// CMS
+    Gbk,
+    /// TMS
+    Big5,
+    /// JMS
+    ShiftJis,
+    /// guess code page of each string by the characters it decoded to.
+    Auto,
+}
+
+const AUTO_CANDIDATES: [CodePage; 4] = [
+    CodePage::EucKr,
+    CodePage::Gbk,
+    CodePage::Big5,
+    CodePage::ShiftJis,
+];
+
+impl CodePage {
+    #[inline]
+    fn encoding(&self) -> &'static Encoding {
+        match self {
+            CodePage::Windows1252 | CodePage::Auto => encoding_rs::WINDOWS_1252,
+            CodePage::EucKr => encoding_rs::EUC_KR,
+            CodePage::Gbk => encoding_rs::GBK,
+            CodePage::Big5 => encoding_rs::BIG5,
+            CodePage::ShiftJis => encoding_rs::SHIFT_JIS,
+        }
+    }
+
+    /// `None` when `bytes` is not valid in this code page.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        if let CodePage::Auto = self {
+            return detect(bytes).decode(bytes);
+        }
+        let (str, had_error) = self.encoding().decode_without_bom_handling(bytes);
+        if had_error {
+            return None;
+        }
+        Some(str.into_owned())
+    }
+
+    #[inline]
+    fn is_native(&self, c: char) -> bool {
+        match self {
+            CodePage::EucKr => {
+                matches!(c, '\u{AC00}'..='\u{D7A3}' | '\u{3130}'..='\u{318F}') && self.is_base(c)
+            }
+            CodePage::Gbk => matches!(c, '\u{4E00}'..='\u{9FFF}') && self.is_base(c),
+            CodePage::Big5 => matches!(c, '\u{4E00}'..='\u{9FFF}'),
+            // half-width katakana are single bytes `0xa1..=0xdf`, lead and
+            // trail bytes of the other code pages decode to them.
+            CodePage::ShiftJis => matches!(c, '\u{3040}'..='\u{30FF}' | '\u{4E00}'..='\u{9FFF}'),
+            _ => false,
+        }
+    }
+
+    /// KS X 1001 and GB2312 characters have both bytes in `0xa1..=0xfe`, the
+    /// extensions of CP949 and GBK share lead bytes with the other code pages.
+    #[inline]
+    fn is_base(&self, c: char) -> bool {
+        let mut buf = [0; 4];
+        let (bytes, _, _) = self.encoding().encode(c.encode_utf8(&mut buf));
+        bytes.iter().all(|b| *b >= 0xa1)
+    }
+}
+
+/// pick the candidate which decodes most characters into its native script.
+fn detect(bytes: &[u8]) -> CodePage {
+    if bytes.is_ascii() {
+        return CodePage::Windows1252;
+    }
+    AUTO_CANDIDATES
+        .iter()
+        .filter_map(|cp| {
+            let str = cp.decode(bytes)?;
+            let native = str.chars().filter(|c| cp.is_native(*c)).count();
+            (native > 0).then_some((native, *cp))
+        })
+        // `max_by_key` returns the last max element, keep candidates order on tie
+        .rev()
+        .max_by_key(|(native, _)| *native)
+        .map(|(_, cp)| cp)
+        .unwrap_or(CodePage::Windows1252)
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct StringDecoder {
+    pub code_page: CodePage,
+    /// instead of `Error::InvalidCharacter`, map each byte to the char with
+    /// same value when decode failed, raw bytes can be taken back by `c as u8`.
+    pub lossy: bool,
+}
+
+impl StringDecoder {
+    #[inline]
+    pub fn new(code_page: CodePage, lossy: bool) -> Self {
+        Self { code_page, lossy }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, Error> {
+        match self.code_page.decode(bytes) {
+            Some(str) => Ok(str),
+            None if self.lossy => Ok(bytes.iter().map(|b| *b as char).collect()),
+            None => Err(Error::InvalidCharacter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EUC_KR: &[u8] = &[0xb8, 0xde, 0xc0, 0xcc, 0xc7, 0xc3];
+    const GBK: &[u8] = &[0xc3, 0xb0, 0xcf, 0xd5, 0xb5, 0xba];
+    const BIG5: &[u8] = &[0xb7, 0xac, 0xa4, 0xa7, 0xa8, 0xa6];
+    const SHIFT_JIS: &[u8] = &[0x83, 0x81, 0x83, 0x43, 0x83, 0x76, 0x83, 0x8b];
+    // lead byte followed by an invalid trail byte in every candidate
+    const INVALID: &[u8] = &[0xc8, 0xff, 0x41];
+
+    #[test]
+    fn decode_native_strings() {
+        assert_eq!(CodePage::EucKr.decode(EUC_KR).unwrap(), "메이플");
+        assert_eq!(CodePage::Gbk.decode(GBK).unwrap(), "冒险岛");
+        assert_eq!(CodePage::Big5.decode(BIG5).unwrap(), "楓之谷");
+        assert_eq!(CodePage::ShiftJis.decode(SHIFT_JIS).unwrap(), "メイプル");
+        assert_eq!(CodePage::Windows1252.decode(b"caf\xe9").unwrap(), "café");
+        assert_eq!(CodePage::EucKr.decode(INVALID), None);
+    }
+
+    #[test]
+    fn auto_detect() {
+        assert_eq!(detect(b"snail"), CodePage::Windows1252);
+        assert_eq!(detect(EUC_KR), CodePage::EucKr);
+        assert_eq!(detect(SHIFT_JIS), CodePage::ShiftJis);
+        assert_eq!(CodePage::Auto.decode(EUC_KR).unwrap(), "메이플");
+        assert_eq!(CodePage::Auto.decode(SHIFT_JIS).unwrap(), "メイプル");
+        assert_eq!(CodePage::Auto.decode(b"snail").unwrap(), "snail");
+        // nothing decodes to a native script
+        assert_eq!(detect(INVALID), CodePage::Windows1252);
+    }
+
+    #[test]
+    fn lossy_keeps_raw_bytes() {
+        let strict = StringDecoder::new(CodePage::EucKr, false);
+        assert!(matches!(
+            strict.decode(INVALID),
+            Err(Error::InvalidCharacter)
+        ));
+        assert_eq!(strict.decode(EUC_KR).unwrap(), "메이플");
+
+        let lossy = StringDecoder::new(CodePage::EucKr, true);
+        let str = lossy.decode(INVALID).unwrap();
+        let raw = str.chars().map(|c| c as u8).collect::<Vec<_>>();
+        assert_eq!(raw, INVALID);
+        assert_eq!(lossy.decode(EUC_KR).unwrap(), "메이플");
+    }
+}
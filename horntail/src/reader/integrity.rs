@@ -0,0 +1,42 @@
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Mismatch {
+    Checksum {
+        expected: i32,
+        actual: i32,
+    },
+    /// data range `end` beyond file length `len`.
+    Truncated {
+        end: usize,
+        len: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct IntegrityIssue {
+    pub name: String,
+    pub mismatch: Mismatch,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub checked: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn report(&mut self, name: &str, mismatch: Option<Mismatch>) {
+        self.checked += 1;
+        if let Some(mismatch) = mismatch {
+            self.issues.push(IntegrityIssue {
+                name: name.to_owned(),
+                mismatch,
+            });
+        }
+    }
+}
@@ -1,11 +1,15 @@
 mod accessor;
 mod binary;
-mod pack;
+mod code_page;
+mod integrity;
+pub(crate) mod pack;
 mod snow2;
 mod source;
 pub mod wizet;
 
 pub use accessor::{Accessor, StringKind, seek_back};
 pub use binary::{BinaryAccessor, BinaryBuilder};
-pub use pack::{PackEntries, PackEntry, PackFile};
+pub use code_page::{CodePage, StringDecoder};
+pub use integrity::{IntegrityIssue, IntegrityReport, Mismatch};
+pub use pack::{PackEntries, PackEntry, PackFile, PackLayout};
 pub use source::Source;
@@ -1,16 +1,101 @@
 use crate::crypto::MapleTableNone;
 use crate::error::{Error, Result};
+use crate::reader::integrity::{IntegrityReport, Mismatch};
 use crate::reader::snow2::{Snow2, align_size};
 use crate::reader::{Accessor, BinaryAccessor, Source};
 use std::io::{Read, SeekFrom};
 use std::ops::Deref;
 use std::path::Path;
 
-const SUPPORTED_VERSION: u8 = 2;
 const BLOCK_SIZE: usize = 0x400;
+const ENTRY_KEY_SIZE: usize = 16;
+// checksum, flags, offset, size, size_aligned
+const ENTRY_REQUIRED_FIELDS: usize = 5;
+// hash, version, entry_count
+const HEADER_REQUIRED_SIZE: usize = 9;
+// bounds of layouts probed by `PackFile::detect`
+const MAX_HEADER_FIELDS: usize = 4;
+const MAX_ENTRY_FIELDS: usize = 16;
+const MAX_ENTRY_NAME_LEN: usize = 0x1000;
+
+/// header and entry table layout of a pack file revision.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PackLayout {
+    /// count of `i32` fields follow entry count in header, summed into hash.
+    pub header_fields: usize,
+    /// count of `i32` fields between entry name and entry key.
+    pub entry_fields: usize,
+}
+
+impl PackLayout {
+    pub const V2: PackLayout = PackLayout {
+        header_fields: 0,
+        entry_fields: 7,
+    };
+
+    /// known layout of header `version`.
+    pub fn from_version(version: u8) -> Option<PackLayout> {
+        match version {
+            2 => Some(Self::V2),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn header_size(&self) -> usize {
+        HEADER_REQUIRED_SIZE + self.header_fields * 4
+    }
+
+    #[inline]
+    fn entry_size(&self, name_len: usize) -> usize {
+        name_len * 2 + self.entry_fields * 4 + ENTRY_KEY_SIZE
+    }
+}
+
+enum LayoutSource {
+    Known,
+    Given(PackLayout),
+    Probe,
+}
+
+struct PackHeader {
+    hash: i32,
+    version: u8,
+    entry_count: i32,
+    fields: Vec<i32>,
+}
+
+impl PackHeader {
+    fn try_read<T: Read>(accessor: T, key: [u8; 16], header_fields: usize) -> Result<PackHeader> {
+        let mut snow2 = Snow2::new(accessor, key);
+        let mut temp = vec![0; align_size(HEADER_REQUIRED_SIZE + header_fields * 4)];
+        snow2.read_exact(&mut temp)?;
+        let mut reader = BinaryAccessor::new(MapleTableNone, temp);
+        Ok(PackHeader {
+            hash: reader.get_i32_le(),
+            version: reader.get_u8(),
+            entry_count: reader.get_i32_le(),
+            fields: (0..header_fields).map(|_| reader.get_i32_le()).collect(),
+        })
+    }
+
+    fn verify(&self, hash_salt_len: i32, salt_bytes: &[u8]) -> bool {
+        let hash = self.fields.iter().fold(
+            hash_salt_len
+                .wrapping_add(self.version as i32)
+                .wrapping_add(self.entry_count),
+            |acc, field| acc.wrapping_add(*field),
+        );
+        salt_bytes.chunks(2).fold(hash, |acc, salt| {
+            acc.wrapping_add(u16::from_le_bytes([salt[0], salt[1]]) as i32)
+        }) == self.hash
+    }
+}
 
 pub struct PackFile {
     source: Source,
+    version: u8,
+    layout: PackLayout,
     entry_key: [u8; 16],
     entry_pos: usize,
     entry_count: usize,
@@ -20,7 +105,32 @@ pub struct PackFile {
 impl PackFile {
     pub const EXTENSION: &'static str = "ms";
 
+    /// open pack file with the known layout of its header version, unknown
+    /// versions are rejected with [`Error::InvalidVersion`].
+    #[inline]
     pub fn new<P: AsRef<Path>>(path: P) -> Result<PackFile> {
+        Self::open(path, LayoutSource::Known)
+    }
+
+    /// open pack file with given `layout` for revisions don't have a known layout.
+    #[inline]
+    pub fn with_layout<P: AsRef<Path>>(path: P, layout: PackLayout) -> Result<PackFile> {
+        Self::open(path, LayoutSource::Given(layout))
+    }
+
+    /// open pack file of unknown revision by guessing its layout.
+    ///
+    /// this is a heuristic: layouts of up to 4 header fields and 16 entry
+    /// fields are tried in order, and the first one whose header hash and
+    /// first entry checksum agree is taken. it can pick a wrong layout for
+    /// revisions that change more than the field counts, prefer
+    /// [`PackFile::with_layout`] when the layout is known.
+    #[inline]
+    pub fn detect<P: AsRef<Path>>(path: P) -> Result<PackFile> {
+        Self::open(path, LayoutSource::Probe)
+    }
+
+    fn open<P: AsRef<Path>>(path: P, layout: LayoutSource) -> Result<PackFile> {
         let source = Source::new(path.as_ref());
 
         let data = source.open()?;
@@ -58,31 +168,8 @@ impl PackFile {
             .collect::<Vec<_>>();
 
         let pos = accessor.pos();
-        let mut snow2 = Snow2::new(accessor, snow_cipher_key.try_into().unwrap());
-
-        let mut temp = [0; 12];
-        snow2.read_exact(&mut temp)?;
-        let mut snow2 = BinaryAccessor::new(MapleTableNone, temp);
-
-        let hash = snow2.get_i32_le();
-        let version = snow2.get_u8();
-        let entry_count = snow2.get_i32_le();
+        let snow_cipher_key: [u8; 16] = snow_cipher_key.try_into().unwrap();
 
-        if version != SUPPORTED_VERSION {
-            return Err(Error::InvalidVersion);
-        }
-
-        if salt_bytes.chunks(2).fold(
-            hash_salt_len
-                .wrapping_add(version as i32)
-                .wrapping_add(entry_count),
-            |acc, salt| acc.wrapping_add(u16::from_le_bytes([salt[0], salt[1]]) as i32),
-        ) != hash
-        {
-            return Err(Error::BrokenFile);
-        }
-
-        let entry_pos = pos + 9 + filename_sum % 212 + 33;
         let salt_size = file_name_with_salt_len as usize;
         let mut entry_key = [0; 16];
         entry_key.iter_mut().enumerate().for_each(|(index, b)| {
@@ -102,11 +189,70 @@ impl PackFile {
             .map(|x| (x as u8).wrapping_sub(b'0'))
             .collect::<Vec<_>>();
 
+        let entry_pos = |layout: &PackLayout| pos + layout.header_size() + filename_sum % 212 + 33;
+
+        // version is at the same place in every revision
+        accessor.seek(SeekFrom::Start(pos as u64));
+        let version = PackHeader::try_read(&mut accessor, snow_cipher_key, 0)?.version;
+
+        let layout = match layout {
+            LayoutSource::Given(layout) => layout,
+            LayoutSource::Known => {
+                PackLayout::from_version(version).ok_or(Error::InvalidVersion)?
+            }
+            LayoutSource::Probe => {
+                // probe layouts of unknown revision, the header hash and the
+                // checksum of first entry must agree.
+                let mut detected = None;
+                'probe: for header_fields in 0..=MAX_HEADER_FIELDS {
+                    accessor.seek(SeekFrom::Start(pos as u64));
+                    let Ok(header) =
+                        PackHeader::try_read(&mut accessor, snow_cipher_key, header_fields)
+                    else {
+                        break;
+                    };
+                    if !header.verify(hash_salt_len, &salt_bytes) {
+                        continue;
+                    }
+                    for entry_fields in ENTRY_REQUIRED_FIELDS..=MAX_ENTRY_FIELDS {
+                        let layout = PackLayout {
+                            header_fields,
+                            entry_fields,
+                        };
+                        if header.entry_count <= 0 {
+                            detected = Some(layout);
+                            break 'probe;
+                        }
+                        accessor.seek(SeekFrom::Start(entry_pos(&layout) as u64));
+                        let mut snow2 = Snow2::new(&mut accessor, entry_key);
+                        if read_entry(&mut snow2, layout, &kh_digits)
+                            .is_ok_and(|(entry, _)| entry.verify())
+                        {
+                            detected = Some(layout);
+                            break 'probe;
+                        }
+                    }
+                }
+                detected.ok_or(Error::InvalidVersion)?
+            }
+        };
+        if layout.entry_fields < ENTRY_REQUIRED_FIELDS {
+            return Err(Error::InvalidArgument);
+        }
+
+        accessor.seek(SeekFrom::Start(pos as u64));
+        let header = PackHeader::try_read(&mut accessor, snow_cipher_key, layout.header_fields)?;
+        if !header.verify(hash_salt_len, &salt_bytes) {
+            return Err(Error::BrokenFile);
+        }
+
         Ok(PackFile {
             source,
+            version,
+            layout,
             entry_key,
-            entry_pos,
-            entry_count: entry_count as usize,
+            entry_pos: entry_pos(&layout),
+            entry_count: header.entry_count as usize,
             image_key_salt: kh_digits,
         })
     }
@@ -115,10 +261,41 @@ impl PackFile {
         &self.source
     }
 
+    #[inline]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    #[inline]
+    pub fn layout(&self) -> PackLayout {
+        self.layout
+    }
+
     #[inline]
     pub fn entries(&self) -> Result<PackEntries> {
         PackEntries::try_from(self)
     }
+
+    /// verify every entry checksum and data range of the file.
+    pub fn verify(&self) -> Result<IntegrityReport> {
+        let len = self.source.open()?.len();
+        let mut report = IntegrityReport::default();
+        for entry in self.entries()?.iter() {
+            let end = entry.offset as usize + entry.size_aligned as usize;
+            let mismatch = if end > len {
+                Some(Mismatch::Truncated { end, len })
+            } else if !entry.verify() {
+                Some(Mismatch::Checksum {
+                    expected: entry.checksum,
+                    actual: entry.calc_checksum(),
+                })
+            } else {
+                None
+            };
+            report.report(&entry.name, mismatch);
+        }
+        Ok(report)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -126,28 +303,72 @@ pub struct PackEntry {
     pub name: String,
     pub flags: i32,
     pub checksum: i32,
-    pub offset: i32,
-    pub size: i32,
-    pub size_aligned: i32,
+    pub offset: u64,
+    pub size: u32,
+    pub size_aligned: u32,
     pub unk1: i32,
     pub unk2: i32,
+    /// fields follow `unk2` of newer revisions.
+    pub extra: Vec<i32>,
     pub key: [u8; 16],
+    /// offset in blocks stored in entry table.
+    pub block: i32,
+    /// key stored in entry table which `key` derived from.
+    pub entry_key: [u8; 16],
 }
 
 impl PackEntry {
+    #[inline]
+    pub fn calc_checksum(&self) -> i32 {
+        self.flags
+            .wrapping_add(self.block)
+            .wrapping_add(self.size as i32)
+            .wrapping_add(self.size_aligned as i32)
+            .wrapping_add(self.unk1)
+            .wrapping_add(
+                self.entry_key
+                    .iter()
+                    .fold(0i32, |acc, k| acc.wrapping_add(*k as i32)),
+            )
+    }
+
+    #[inline]
+    pub fn verify(&self) -> bool {
+        self.calc_checksum() == self.checksum
+    }
+
     #[inline]
     pub fn decrypt_from<T: Accessor + Read>(&self, accessor: &mut T) -> Result<Vec<u8>> {
+        let (mut stream_crypto, _) = self.prepare(accessor)?;
+        let mut buffer = vec![0; self.size_aligned as usize];
+        stream_crypto.read_exact(buffer.as_mut_slice())?;
+        Ok(buffer)
+    }
+
+    /// decrypt the leading block of data only, enough for the image header.
+    #[inline]
+    pub fn decrypt_header_from<T: Accessor + Read>(&self, accessor: &mut T) -> Result<Vec<u8>> {
+        let (mut stream_crypto, prepare_size) = self.prepare(accessor)?;
+        let mut buffer = vec![0; prepare_size];
+        stream_crypto.read_exact(buffer.as_mut_slice())?;
+        Ok(buffer)
+    }
+
+    fn prepare<'a, T: Accessor + Read>(
+        &self,
+        accessor: &'a mut T,
+    ) -> Result<(Snow2<&'a mut T>, usize)> {
         let prepare_size = align_size(self.size.min(0x400) as usize);
-        accessor.seek(SeekFrom::Start(self.offset as u64));
+        accessor.seek(SeekFrom::Start(self.offset));
         let mut p_buffer = vec![0; prepare_size];
         let mut prepare = Snow2::new(accessor, self.key);
         prepare.read_exact(&mut p_buffer)?;
         let stream = prepare.into_inner();
-        stream.seek(SeekFrom::Start((self.offset + prepare_size as i32) as u64));
-        let mut stream_crypto = Snow2::with_buffer(stream, &p_buffer, self.key)?;
-        let mut buffer = vec![0; self.size_aligned as usize];
-        stream_crypto.read_exact(buffer.as_mut_slice())?;
-        Ok(buffer)
+        stream.seek(SeekFrom::Start(self.offset + prepare_size as u64));
+        Ok((
+            Snow2::with_buffer(stream, &p_buffer, self.key)?,
+            prepare_size,
+        ))
     }
 }
 
@@ -174,62 +395,397 @@ impl TryFrom<&PackFile> for PackEntries {
         let mut snow2 = Snow2::new(stream, file.entry_key);
         let mut entries = Vec::with_capacity(file.entry_count);
         for _ in 0..file.entry_count {
-            let mut temp = [0; 4];
-            snow2.read_exact(&mut temp)?;
-            let entry_name_len = i32::from_le_bytes(temp);
-            let mut temp = vec![0; (entry_name_len as usize * 2) + 44];
-            snow2.read_exact(&mut temp)?;
-            let temp_size = temp.len();
-            let mut reader = BinaryAccessor::new(MapleTableNone, temp);
-            let name = reader.get_utf16_string(entry_name_len as usize);
-            let mut entry = PackEntry {
-                name,
-                checksum: reader.get_i32_le(),
-                flags: reader.get_i32_le(),
-                offset: reader.get_i32_le().wrapping_mul(BLOCK_SIZE as i32),
-                size: reader.get_i32_le(),
-                size_aligned: reader.get_i32_le(),
-                unk1: reader.get_i32_le(),
-                unk2: reader.get_i32_le(),
-                key: Default::default(),
-            };
-            // let calc_checksum = flags
-            //     .wrapping_add(start_pos)
-            //     .wrapping_add(size)
-            //     .wrapping_add(size_aligned)
-            //     .wrapping_add(unk1)
-            //     .wrapping_add(entry_key.iter().copied().fold(0, |acc, k| acc + k as i32));
-            reader.copy_to_slice(&mut entry.key);
-
-            let salt = &file.image_key_salt;
-            let salt_len = file.image_key_salt.len();
-            let mut image_key = [0; 16];
-            let entry_name = entry.name.as_bytes();
-            let entry_name_len = entry_name.len();
-            let entry_key = &entry.key[..];
-            let entry_key_len = entry_key.len();
-            image_key.iter_mut().enumerate().for_each(|(i, k)| {
-                *k = i.wrapping_add(
-                    (entry_name[i % entry_name_len] as usize).wrapping_mul(
-                        (salt[i % salt_len] as usize % 2)
-                            .wrapping_add(
-                                entry_key[(salt[(i + 2) % salt_len] as usize + i) % entry_key_len]
-                                    as usize,
-                            )
-                            .wrapping_add((salt[(i + 1) % salt_len] as usize + i) % 5),
-                    ),
-                ) as u8;
-            });
-            entry.key = image_key;
-            offset += temp_size + 4;
+            let (entry, size) = read_entry(&mut snow2, file.layout, &file.image_key_salt)?;
+            offset += size;
             entries.push(entry)
         }
 
         let image_data_off = (offset + BLOCK_SIZE - 1) & !(BLOCK_SIZE - 1);
         entries
             .iter_mut()
-            .for_each(|x| x.offset += image_data_off as i32);
+            .for_each(|x| x.offset += image_data_off as u64);
 
         Ok(PackEntries { entries })
     }
 }
+
+/// read next entry of table, returns the entry and the size it takes.
+fn read_entry<T: Read>(
+    snow2: &mut Snow2<T>,
+    layout: PackLayout,
+    image_key_salt: &[u8],
+) -> Result<(PackEntry, usize)> {
+    let mut temp = [0; 4];
+    snow2.read_exact(&mut temp)?;
+    let entry_name_len = i32::from_le_bytes(temp);
+    if !(1..=MAX_ENTRY_NAME_LEN as i32).contains(&entry_name_len) {
+        return Err(Error::BrokenFile);
+    }
+    let mut temp = vec![0; layout.entry_size(entry_name_len as usize)];
+    snow2.read_exact(&mut temp)?;
+    let temp_size = temp.len();
+    let mut reader = BinaryAccessor::new(MapleTableNone, temp);
+    let name = reader.get_utf16_string(entry_name_len as usize);
+    let mut fields = (0..layout.entry_fields).map(|_| reader.get_i32_le());
+    let checksum = fields.next().unwrap_or_default();
+    let flags = fields.next().unwrap_or_default();
+    let block = fields.next().unwrap_or_default();
+    let mut entry = PackEntry {
+        name,
+        checksum,
+        flags,
+        offset: block as u32 as u64 * BLOCK_SIZE as u64,
+        size: fields.next().unwrap_or_default() as u32,
+        size_aligned: fields.next().unwrap_or_default() as u32,
+        unk1: fields.next().unwrap_or_default(),
+        unk2: fields.next().unwrap_or_default(),
+        extra: fields.collect(),
+        key: Default::default(),
+        block,
+        entry_key: Default::default(),
+    };
+    reader.copy_to_slice(&mut entry.entry_key);
+
+    let salt = image_key_salt;
+    let salt_len = image_key_salt.len();
+    let mut image_key = [0; 16];
+    let entry_name = entry.name.as_bytes();
+    let entry_name_len = entry_name.len();
+    let entry_key = &entry.entry_key[..];
+    let entry_key_len = entry_key.len();
+    image_key.iter_mut().enumerate().for_each(|(i, k)| {
+        *k = i.wrapping_add(
+            (entry_name[i % entry_name_len] as usize).wrapping_mul(
+                (salt[i % salt_len] as usize % 2)
+                    .wrapping_add(
+                        entry_key[(salt[(i + 2) % salt_len] as usize + i) % entry_key_len] as usize,
+                    )
+                    .wrapping_add((salt[(i + 1) % salt_len] as usize + i) % 5),
+            ),
+        ) as u8;
+    });
+    entry.key = image_key;
+    Ok((entry, temp_size + 4))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::crypto::Snow2 as Snow2Crypto;
+    use std::fs;
+    use std::io::{Seek, Write};
+    use std::path::PathBuf;
+
+    pub(crate) struct TestEntry<'a> {
+        pub name: &'a str,
+        pub data: &'a [u8],
+    }
+
+    pub(crate) fn temp_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("horntail-{}-{tag}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn encrypt(key: [u8; 16], data: &mut [u8]) {
+        Snow2Crypto::new(key, [0; 4]).crypt(data, true);
+    }
+
+    /// write pack file of `layout`, `block_base` shifts every image by blocks.
+    pub(crate) fn write_pack(
+        path: &Path,
+        version: u8,
+        layout: PackLayout,
+        header_fields: &[i32],
+        entries: &[TestEntry],
+        block_base: u32,
+    ) {
+        assert_eq!(header_fields.len(), layout.header_fields);
+        let filename = path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_ascii_lowercase();
+        let salt = "salt";
+        let rand_byte_size = filename.chars().map(|c| c as usize).sum::<usize>() % 312 + 30;
+        let rand_bytes = (0..rand_byte_size)
+            .map(|i| (i * 31 + 7) as u8)
+            .collect::<Vec<_>>();
+        let hash_salt_len = (salt.len() as u8 ^ rand_bytes[0]) as i32;
+        let salt_bytes = salt
+            .bytes()
+            .zip(rand_bytes.iter())
+            .flat_map(|(c, r)| [c ^ r, 0])
+            .collect::<Vec<_>>();
+
+        let mut file = rand_bytes.clone();
+        file.extend(hash_salt_len.to_le_bytes());
+        file.extend(&salt_bytes);
+        let pos = file.len();
+
+        let filename_with_salt = format!("{filename}{salt}").into_bytes();
+        let len = filename_with_salt.len();
+        let header_key: [u8; 16] = std::array::from_fn(|i| filename_with_salt[i % len] + i as u8);
+        let entry_key: [u8; 16] = std::array::from_fn(|i| {
+            i.wrapping_add((i % 3 + 2).wrapping_mul(filename_with_salt[len - 1 - i % len] as usize))
+                as u8
+        });
+
+        let entry_count = entries.len() as i32;
+        let hash = salt_bytes.chunks(2).fold(
+            header_fields.iter().fold(
+                hash_salt_len
+                    .wrapping_add(version as i32)
+                    .wrapping_add(entry_count),
+                |acc, f| acc.wrapping_add(*f),
+            ),
+            |acc, s| acc.wrapping_add(u16::from_le_bytes([s[0], s[1]]) as i32),
+        );
+        let mut header = hash.to_le_bytes().to_vec();
+        header.push(version);
+        header.extend(entry_count.to_le_bytes());
+        header_fields
+            .iter()
+            .for_each(|f| header.extend(f.to_le_bytes()));
+        header.resize(align_size(header.len()), 0);
+        encrypt(header_key, &mut header);
+        file.extend(header);
+
+        let filename_sum = filename.chars().map(|c| c as usize * 3).sum::<usize>();
+        file.resize(pos + layout.header_size() + filename_sum % 212 + 33, 0);
+
+        let mut table = Vec::new();
+        let mut block = block_base;
+        for (index, entry) in entries.iter().enumerate() {
+            let name = entry.name.encode_utf16().collect::<Vec<_>>();
+            let key = [index as u8 + 1; 16];
+            let size = entry.data.len() as u32;
+            let size_aligned = align_size(entry.data.len()) as u32;
+            let checksum = (block as i32)
+                .wrapping_add(size as i32)
+                .wrapping_add(size_aligned as i32)
+                .wrapping_add(key.iter().map(|k| *k as i32).sum::<i32>());
+            let mut fields = vec![checksum, 0, block as i32, size as i32, size_aligned as i32];
+            fields.resize(layout.entry_fields, 0);
+
+            table.extend((name.len() as i32).to_le_bytes());
+            name.iter().for_each(|c| table.extend(c.to_le_bytes()));
+            fields.iter().for_each(|f| table.extend(f.to_le_bytes()));
+            table.extend(key);
+            block += (size_aligned as usize).div_ceil(BLOCK_SIZE).max(1) as u32;
+        }
+        let table_end = file.len() + table.len();
+        table.resize(align_size(table.len()), 0);
+        encrypt(entry_key, &mut table);
+        // keep the padding, table is read in words
+        file.extend(table);
+        fs::write(path, &file).unwrap();
+
+        // image keys derive from the table, read them back to encrypt images.
+        let pack = PackFile::with_layout(path, layout).unwrap();
+        let parsed = pack.entries().unwrap();
+        let image_data_off = (table_end + BLOCK_SIZE - 1) & !(BLOCK_SIZE - 1);
+        let mut file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        for (entry, parsed) in entries.iter().zip(parsed.iter()) {
+            let mut data = entry.data.to_vec();
+            data.resize(parsed.size_aligned as usize, 0);
+            // the leading block is decrypted twice, see `PackEntry::prepare`
+            encrypt(parsed.key, &mut data);
+            let prepare_size = align_size(parsed.size.min(0x400) as usize);
+            encrypt(parsed.key, &mut data[..prepare_size]);
+            assert!(parsed.offset >= image_data_off as u64);
+            file.seek(std::io::SeekFrom::Start(parsed.offset)).unwrap();
+            file.write_all(&data).unwrap();
+        }
+    }
+
+    pub(crate) fn read_back(pack: &PackFile) -> Vec<(String, Vec<u8>)> {
+        let mut accessor = BinaryAccessor::new(MapleTableNone, pack.source().open().unwrap());
+        pack.entries()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                assert!(e.verify());
+                let mut data = e.decrypt_from(&mut accessor).unwrap();
+                data.truncate(e.size as usize);
+                (e.name.clone(), data)
+            })
+            .collect()
+    }
+
+    const ENTRIES: &[TestEntry] = &[
+        TestEntry {
+            name: "Mob/0100100.img",
+            data: b"green snail",
+        },
+        TestEntry {
+            name: "Mob/0100101.img",
+            data: &[0x5a; 0x900],
+        },
+    ];
+
+    fn check_version(tag: &str, version: u8, layout: PackLayout, header_fields: &[i32]) {
+        let dir = temp_dir(tag);
+        let path = dir.join("Data_000.ms");
+        write_pack(&path, version, layout, header_fields, ENTRIES, 0);
+
+        let pack = if PackLayout::from_version(version).is_some() {
+            PackFile::new(&path).unwrap()
+        } else {
+            assert!(matches!(PackFile::new(&path), Err(Error::InvalidVersion)));
+            PackFile::detect(&path).unwrap()
+        };
+        let entries = read_back(&pack);
+        assert_eq!(pack.version(), version);
+        assert_eq!(pack.layout(), layout);
+        assert_eq!(entries.len(), ENTRIES.len());
+        for ((name, data), expected) in entries.iter().zip(ENTRIES) {
+            assert_eq!(name, expected.name);
+            assert_eq!(data.as_slice(), expected.data);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn known_version_2() {
+        check_version("pack-v2", 2, PackLayout::V2, &[]);
+    }
+
+    #[test]
+    fn detect_version_1() {
+        let layout = PackLayout {
+            header_fields: 0,
+            entry_fields: ENTRY_REQUIRED_FIELDS,
+        };
+        check_version("pack-v1", 1, layout, &[]);
+    }
+
+    #[test]
+    fn detect_version_3() {
+        let layout = PackLayout {
+            header_fields: 1,
+            entry_fields: 8,
+        };
+        check_version("pack-v3", 3, layout, &[0x1234]);
+    }
+
+    #[test]
+    fn detect_version_4() {
+        let layout = PackLayout {
+            header_fields: 2,
+            entry_fields: 9,
+        };
+        check_version("pack-v4", 4, layout, &[0, -1]);
+    }
+
+    #[test]
+    fn undetectable_version() {
+        let dir = temp_dir("pack-unknown");
+        let path = dir.join("Data_000.ms");
+        let layout = PackLayout {
+            header_fields: 0,
+            entry_fields: MAX_ENTRY_FIELDS + 1,
+        };
+        write_pack(&path, 9, layout, &[], ENTRIES, 0);
+        assert!(matches!(PackFile::new(&path), Err(Error::InvalidVersion)));
+        assert!(matches!(
+            PackFile::detect(&path),
+            Err(Error::InvalidVersion)
+        ));
+        assert_eq!(
+            PackFile::with_layout(&path, layout).unwrap().layout(),
+            layout
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn broken_header_hash() {
+        let dir = temp_dir("pack-broken");
+        let path = dir.join("Data_000.ms");
+        write_pack(&path, 2, PackLayout::V2, &[], ENTRIES, 0);
+        let mut data = fs::read(&path).unwrap();
+        // salt bytes are summed into the hash
+        let rand_byte_size = "data_000.ms".chars().map(|c| c as usize).sum::<usize>() % 312 + 30;
+        data[rand_byte_size + 5] ^= 1;
+        fs::write(&path, data).unwrap();
+        assert!(matches!(PackFile::new(&path), Err(Error::BrokenFile)));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn verify_checksum() {
+        let dir = temp_dir("pack-checksum");
+        let path = dir.join("Data_000.ms");
+        write_pack(&path, 2, PackLayout::V2, &[], ENTRIES, 0);
+        let pack = PackFile::new(&path).unwrap();
+        assert!(pack.verify().unwrap().is_ok());
+
+        // checksum of the first entry follows its name length and name
+        let at = 4 + ENTRIES[0].name.len() * 2;
+        let mut data = fs::read(&path).unwrap();
+        let table = &mut data[pack.entry_pos..pack.entry_pos + align_size(at + 4)];
+        Snow2Crypto::new(pack.entry_key, [0; 4]).crypt(table, false);
+        table[at] ^= 1;
+        encrypt(pack.entry_key, table);
+        fs::write(&path, data).unwrap();
+
+        let report = PackFile::new(&path).unwrap().verify().unwrap();
+        assert_eq!(report.checked, ENTRIES.len());
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].name, ENTRIES[0].name);
+        let Mismatch::Checksum { expected, actual } = report.issues[0].mismatch else {
+            panic!("{:?}", report.issues[0].mismatch);
+        };
+        assert_eq!(expected ^ actual, 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn verify_truncated() {
+        let dir = temp_dir("pack-truncated");
+        let path = dir.join("Data_000.ms");
+        write_pack(&path, 2, PackLayout::V2, &[], ENTRIES, 0);
+        let len = fs::metadata(&path).unwrap().len() as usize - 0x10;
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len as u64)
+            .unwrap();
+
+        let report = PackFile::new(&path).unwrap().verify().unwrap();
+        assert_eq!(report.checked, ENTRIES.len());
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].name, ENTRIES[1].name);
+        assert_eq!(
+            report.issues[0].mismatch,
+            Mismatch::Truncated {
+                end: len + 0x10,
+                len
+            }
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn entry_beyond_4gib() {
+        let dir = temp_dir("pack-4gib");
+        let path = dir.join("Data_000.ms");
+        // the first image starts right after the 32-bit boundary
+        let block_base = ((1u64 << 32) / BLOCK_SIZE as u64) as u32;
+        write_pack(&path, 2, PackLayout::V2, &[], ENTRIES, block_base);
+
+        let pack = PackFile::new(&path).unwrap();
+        let entries = pack.entries().unwrap();
+        assert!(entries.iter().all(|e| e.offset > u32::MAX as u64));
+        let entries = read_back(&pack);
+        for ((name, data), expected) in entries.iter().zip(ENTRIES) {
+            assert_eq!(name, expected.name);
+            assert_eq!(data.as_slice(), expected.data);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
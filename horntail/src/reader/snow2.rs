@@ -38,7 +38,7 @@ impl<T> Snow2<T> {
         src: &[u8],
         cipher: [u8; SNOW2_128_KEY_SIZE],
     ) -> crate::error::Result<Snow2<T>> {
-        if src.len() % BLOCK_SIZE != 0 {
+        if !src.len().is_multiple_of(BLOCK_SIZE) {
             return Err(Error::UnexpectedData("invalid encrypt buffer".to_owned()));
         }
         let size = src.len();
@@ -1,8 +1,8 @@
-use crate::AccessorOpt;
-use crate::crypto::{MapleTableNone, MapleVersion};
+use crate::crypto::{MapleCipher, MapleTableNone, MapleVersion};
 use crate::error::{Error, Result};
-use crate::reader::Source;
-use crate::reader::{Accessor, BinaryAccessor};
+use crate::reader::integrity::{IntegrityReport, Mismatch};
+use crate::reader::{Accessor, BinaryAccessor, Source};
+use crate::{AccessorOpt, Directories, EntryKind, TryFromAccessor};
 use std::io::SeekFrom;
 use std::path::Path;
 
@@ -103,4 +103,315 @@ impl WizetFile {
             offset: self.offset(),
         }
     }
+
+    /// verify every image checksum and data range of the file.
+    pub fn verify(&self, cipher: Box<dyn MapleCipher>) -> Result<IntegrityReport> {
+        let mut accessor = BinaryAccessor::from_boxed(cipher, self.source.open()?);
+        let mut report = IntegrityReport::default();
+        verify_directories(self.accessor_opt(), &mut accessor, "", &mut report)?;
+        Ok(report)
+    }
+}
+
+fn verify_directories(
+    opt: AccessorOpt,
+    accessor: &mut dyn Accessor,
+    prefix: &str,
+    report: &mut IntegrityReport,
+) -> Result<()> {
+    accessor.try_seek(SeekFrom::Start(opt.offset as u64))?;
+    let directories = Directories::try_from_accessor(opt, accessor)?;
+    for dir in directories.iter() {
+        let name = format!("{prefix}{}", dir.name);
+        if let EntryKind::Unknown = dir.kind {
+            continue;
+        }
+        if let EntryKind::Folder = dir.kind {
+            verify_directories(
+                opt.clone_with_parent(dir.offset, dir.parent_offset),
+                accessor,
+                &format!("{name}/"),
+                report,
+            )?;
+            continue;
+        }
+        let end = dir.parent_offset + dir.size;
+        let mismatch = if end > accessor.len() {
+            Some(Mismatch::Truncated {
+                end,
+                len: accessor.len(),
+            })
+        } else {
+            let actual = dir.calc_checksum(accessor)?;
+            (actual != dir.checksum).then_some(Mismatch::Checksum {
+                expected: dir.checksum,
+                actual,
+            })
+        };
+        report.report(&name, mismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::reader::pack::tests::{TestEntry, temp_dir};
+    use std::fs;
+
+    pub(crate) const VERSION: u16 = 95;
+    const IMAGE: u8 = 4;
+
+    /// write wizet file of `images` in the root folder, image data is
+    /// written as is and strings are encoded without cipher.
+    pub(crate) fn write_wizet(path: &Path, images: &[TestEntry]) {
+        let copyright = b"Package file v1.0 Copyright 2002 Wizet, ZMS";
+        let header_size = 4 + 8 + 4 + copyright.len() + 1;
+        let ver = MapleVersion::from(VERSION);
+        let ver_hash = ver.hash() as u32;
+
+        let mut table = Vec::new();
+        table.push(images.len() as u8);
+        let mut offsets = Vec::new();
+        for image in images {
+            table.push(IMAGE);
+            table.push((image.name.len() as i8).wrapping_neg() as u8);
+            table.extend(
+                image
+                    .name
+                    .bytes()
+                    .enumerate()
+                    .map(|(i, b)| b ^ (0xaa + i as u8)),
+            );
+            let checksum = image.data.iter().fold(0i32, |acc, b| acc + *b as i32);
+            for v in [image.data.len() as i32, checksum] {
+                if (-127..=127).contains(&v) {
+                    table.push(v as i8 as u8);
+                } else {
+                    table.push(i8::MIN as u8);
+                    table.extend(v.to_le_bytes());
+                }
+            }
+            offsets.push(table.len());
+            table.extend([0; 4]);
+        }
+
+        // directory table follows the version hash
+        let table_pos = header_size + 2;
+        let mut data_pos = table_pos + table.len();
+        for (image, at) in images.iter().zip(offsets) {
+            let enc_pos = (table_pos + at - header_size) as u32;
+            let key = (enc_pos ^ u32::MAX)
+                .wrapping_mul(ver_hash)
+                .wrapping_sub(0x581c3f6d);
+            let low = (data_pos as u32).wrapping_sub((header_size as u32).wrapping_shl(1));
+            table[at..at + 4].copy_from_slice(&(key.rotate_left(key & 0x1f) ^ low).to_le_bytes());
+            data_pos += image.data.len();
+        }
+
+        let mut file = WIZET_SIGNATURE.to_le_bytes().to_vec();
+        file.extend(((data_pos - header_size) as u64).to_le_bytes());
+        file.extend((header_size as u32).to_le_bytes());
+        file.extend(copyright);
+        file.push(0);
+        file.extend(ver.hash_enc().to_le_bytes());
+        file.extend(table);
+        images.iter().for_each(|i| file.extend(i.data));
+        fs::write(path, file).unwrap();
+    }
+
+    /// value of an encoded image, strings are ascii and written inline.
+    pub(crate) enum TestValue {
+        Nil,
+        Int(i32),
+        Double(f64),
+        Str(&'static str),
+        Props(Vec<(&'static str, TestValue)>),
+        /// canvas of `format` over raw `data`, compressed when written.
+        Canvas {
+            size: (i32, i32),
+            format: i32,
+            data: Vec<u8>,
+            props: Vec<(&'static str, TestValue)>,
+        },
+        Vector(i32, i32),
+        Uol(&'static str),
+        Sound(Vec<u8>),
+    }
+
+    fn put_string(out: &mut Vec<u8>, s: &str) {
+        assert!(s.is_ascii() && s.len() < 0x80);
+        out.push((s.len() as i8).wrapping_neg() as u8);
+        out.extend(s.bytes().enumerate().map(|(i, b)| b ^ (0xaa + i as u8)));
+    }
+
+    fn put_var(out: &mut Vec<u8>, v: i32) {
+        if (-127..=127).contains(&v) {
+            out.push(v as i8 as u8);
+        } else {
+            out.push(i8::MIN as u8);
+            out.extend(v.to_le_bytes());
+        }
+    }
+
+    fn put_props(out: &mut Vec<u8>, props: &[(&str, TestValue)]) {
+        out.extend([0, 0]);
+        put_var(out, props.len() as i32);
+        for (name, value) in props {
+            out.push(0);
+            put_string(out, name);
+            match value {
+                TestValue::Nil => out.push(0x00),
+                TestValue::Int(v) => {
+                    out.push(0x03);
+                    put_var(out, *v);
+                }
+                TestValue::Double(v) => {
+                    out.push(0x05);
+                    out.extend(v.to_le_bytes());
+                }
+                TestValue::Str(v) => {
+                    out.extend([0x08, 0]);
+                    put_string(out, v);
+                }
+                image => {
+                    let image = encode_image(image);
+                    out.push(0x09);
+                    out.extend((image.len() as i32).to_le_bytes());
+                    out.extend(image);
+                }
+            }
+        }
+    }
+
+    /// image data of `value` as stored in a directory or a property.
+    pub(crate) fn encode_image(value: &TestValue) -> Vec<u8> {
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+
+        let mut out = vec![0x73];
+        match value {
+            TestValue::Props(props) => {
+                put_string(&mut out, "Property");
+                put_props(&mut out, props);
+            }
+            TestValue::Canvas {
+                size,
+                format,
+                data,
+                props,
+            } => {
+                put_string(&mut out, "Canvas");
+                if props.is_empty() {
+                    out.extend([0, 0]);
+                } else {
+                    out.extend([0, 1]);
+                    put_props(&mut out, props);
+                }
+                put_var(&mut out, size.0);
+                put_var(&mut out, size.1);
+                put_var(&mut out, *format);
+                // scale, flags
+                out.extend([0; 5]);
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                let compressed = encoder.finish().unwrap();
+                out.extend((compressed.len() as i32 + 1).to_le_bytes());
+                out.push(0);
+                out.extend(compressed);
+            }
+            TestValue::Vector(x, y) => {
+                put_string(&mut out, "Shape2D#Vector2D");
+                put_var(&mut out, *x);
+                put_var(&mut out, *y);
+            }
+            TestValue::Uol(path) => {
+                put_string(&mut out, "UOL");
+                out.extend([0, 0]);
+                put_string(&mut out, path);
+            }
+            TestValue::Sound(data) => {
+                put_string(&mut out, "Sound_DX8");
+                out.push(0);
+                put_var(&mut out, data.len() as i32);
+                // duration, type without wave format, media type
+                out.extend([0, 1]);
+                out.extend([0; 16 * 3 + 2]);
+                out.extend(data);
+            }
+            _ => panic!("not an image"),
+        }
+        out
+    }
+
+    const IMAGES: &[TestEntry] = &[
+        TestEntry {
+            name: "0100100.img",
+            data: b"#Property\nname = snail\n",
+        },
+        TestEntry {
+            name: "0100101.img",
+            data: b"#Property\nname = blue snail\n",
+        },
+    ];
+
+    fn open(path: &Path) -> WizetFile {
+        WizetFile::new(path, MapleVersion::from(VERSION), false).unwrap()
+    }
+
+    #[test]
+    fn verify_intact() {
+        let dir = temp_dir("wizet-intact");
+        let path = dir.join("Mob.wz");
+        write_wizet(&path, IMAGES);
+        let report = open(&path).verify(Box::new(MapleTableNone)).unwrap();
+        assert_eq!(report.checked, IMAGES.len());
+        assert!(report.is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn verify_checksum() {
+        let dir = temp_dir("wizet-checksum");
+        let path = dir.join("Mob.wz");
+        write_wizet(&path, IMAGES);
+        let mut data = fs::read(&path).unwrap();
+        // last byte of the second image
+        let last = data.len() - 1;
+        data[last] = b'!';
+        fs::write(&path, data).unwrap();
+
+        let report = open(&path).verify(Box::new(MapleTableNone)).unwrap();
+        assert_eq!(report.checked, IMAGES.len());
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].name, "0100101.img");
+        let Mismatch::Checksum { expected, actual } = report.issues[0].mismatch else {
+            panic!("{:?}", report.issues[0].mismatch);
+        };
+        assert_eq!(actual - expected, b'!' as i32 - b'\n' as i32);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn verify_truncated() {
+        let dir = temp_dir("wizet-truncated");
+        let path = dir.join("Mob.wz");
+        write_wizet(&path, IMAGES);
+        let mut data = fs::read(&path).unwrap();
+        let len = data.len() - 4;
+        data.truncate(len);
+        // keep the header consistent, the file opens but misses data
+        let data_size = u64::from_le_bytes(data[4..12].try_into().unwrap()) - 4;
+        data[4..12].copy_from_slice(&data_size.to_le_bytes());
+        fs::write(&path, data).unwrap();
+
+        let report = open(&path).verify(Box::new(MapleTableNone)).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].name, "0100101.img");
+        assert_eq!(
+            report.issues[0].mismatch,
+            Mismatch::Truncated { end: len + 4, len }
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -111,17 +111,13 @@ impl App {
                     f.set_cursor_position(text_view_state.cursor())
                 }
 
-                if let Some(image_view_state) = image_view_state.as_mut() {
-                    if image_offset != 0 {
-                        let preview_block = preview_block_template.clone();
-                        let image_view_area = preview_block.inner(layout[1]);
-                        f.render_widget(preview_block, layout[1]);
-                        f.render_stateful_widget(
-                            ImageView::new(),
-                            image_view_area,
-                            image_view_state,
-                        );
-                    }
+                if let Some(image_view_state) = image_view_state.as_mut()
+                    && image_offset != 0
+                {
+                    let preview_block = preview_block_template.clone();
+                    let image_view_area = preview_block.inner(layout[1]);
+                    f.render_widget(preview_block, layout[1]);
+                    f.render_stateful_widget(ImageView::new(), image_view_area, image_view_state);
                 }
             })?;
 
@@ -193,16 +189,15 @@ fn handle_selection(
         .property
         .as_ref()
         .and_then(|x| x.iter().find(|p| p.name == "_outlink"))
+        && let Primitive::String(str) = &prop.value
     {
-        if let Primitive::String(str) = &prop.value {
-            let Some(row) = horntail_view_state.get_by_name_paths(str.as_str()) else {
-                return;
-            };
-            if let Some(canvas) = row.to_canvas() {
-                *image_offset = offset;
-                image_view_state.set_canvas(canvas);
-                return;
-            }
+        let Some(row) = horntail_view_state.get_by_name_paths(str.as_str()) else {
+            return;
+        };
+        if let Some(canvas) = row.to_canvas() {
+            *image_offset = offset;
+            image_view_state.set_canvas(canvas);
+            return;
         }
     }
 
@@ -228,11 +223,9 @@ fn handle_input_event(
 ) {
     if let Event::Key(ke) = event {
         match ke.code {
-            KeyCode::Char('c') => {
-                if ke.modifiers.contains(KeyModifiers::CONTROL) {
-                    *event_mode = EventMode::Normal;
-                    text_view_state.reset();
-                }
+            KeyCode::Char('c') if ke.modifiers.contains(KeyModifiers::CONTROL) => {
+                *event_mode = EventMode::Normal;
+                text_view_state.reset();
             }
             KeyCode::Esc => {
                 *event_mode = EventMode::Normal;
@@ -268,16 +261,14 @@ fn handle_normal_event(
             }
         }
         KeyCode::Char('g') => {
-            if let Some(last) = last_key_event {
-                if let KeyCode::Char('g') = last.code {
-                    horntail_view_state.goto_start();
-                }
+            if let Some(last) = last_key_event
+                && let KeyCode::Char('g') = last.code
+            {
+                horntail_view_state.goto_start();
             }
         }
-        KeyCode::Char('G') => {
-            if ke.modifiers.contains(KeyModifiers::SHIFT) {
-                horntail_view_state.goto_end();
-            }
+        KeyCode::Char('G') if ke.modifiers.contains(KeyModifiers::SHIFT) => {
+            horntail_view_state.goto_end();
         }
         KeyCode::Char('[') => {
             horntail_view_state.goto_prev_expand_node();
@@ -285,17 +276,13 @@ fn handle_normal_event(
         KeyCode::Char(']') => {
             horntail_view_state.goto_next_expand_node();
         }
-        KeyCode::Char('f') => {
-            if ke.modifiers.contains(KeyModifiers::CONTROL) {
-                horntail_view_state.page_down()
-            }
+        KeyCode::Char('f') if ke.modifiers.contains(KeyModifiers::CONTROL) => {
+            horntail_view_state.page_down()
         }
         KeyCode::PageDown => horntail_view_state.page_down(),
         KeyCode::PageUp => horntail_view_state.page_up(),
-        KeyCode::Char('b') => {
-            if ke.modifiers.contains(KeyModifiers::CONTROL) {
-                horntail_view_state.page_up()
-            }
+        KeyCode::Char('b') if ke.modifiers.contains(KeyModifiers::CONTROL) => {
+            horntail_view_state.page_up()
         }
         KeyCode::Char('/') => {
             *ev_mode = EventMode::Input;
@@ -2,8 +2,8 @@ use crate::bundle::analyze::{BASE_NAME, Structure};
 use crate::error::Error;
 use crate::row::{HorntailRow, IndexGroup, IndexKind, build_rows};
 use horntail::crypto::{MapleCipher, MapleVersion};
-use horntail::reader::BinaryBuilder;
 use horntail::reader::wizet::WizetFile;
+use horntail::reader::{BinaryBuilder, StringDecoder};
 use horntail::{AccessorBuilder, AccessorOpt, EntryKind};
 use std::ffi::OsStr;
 use std::fs::File;
@@ -16,6 +16,7 @@ pub struct SharedStructure {
     pub ver_hash: u16,
     pub cipher: Box<dyn MapleCipher>,
     pub no_version: bool,
+    pub decoder: StringDecoder,
     pub structure: Structure,
 }
 
@@ -90,8 +91,10 @@ impl WizetBundle {
                 structure: self.structure.clone(),
                 builder: Some(
                     BinaryBuilder::from_boxed(self.structure.cipher.clone_boxed(), source.open()?)
+                        .with_decoder(self.structure.decoder)
                         .into_boxed(),
                 ),
+                entry: None,
             });
 
             let rows = build_rows(
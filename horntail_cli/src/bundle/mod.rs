@@ -2,6 +2,5 @@ pub mod analyze;
 
 #[allow(clippy::module_inception)]
 mod bundle;
-pub mod pack;
 
 pub use bundle::*;
@@ -6,25 +6,75 @@ mod row;
 mod widget;
 
 use crate::bundle::analyze::{BASE_NAME, Structure, analyze_wizet_structure};
-use crate::bundle::pack::PackBundle;
 use crate::bundle::{SharedStructure, WizetBundle};
 use crate::error::Error;
 use crate::optimize::{string_empty, string_pool_get};
 use crate::row::{HorntailRow, IndexGroup, IndexKind, ROW_FLAG_EXPANDED, ROW_FLAG_INITIALIZED};
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use horntail::EntryKind;
-use horntail::crypto::{MapleCipher, MapleTable, MapleTableNone, MapleVersion};
-use horntail::reader::PackFile;
+use horntail::consts::{MAPLE_VECTOR_NONE, Region};
+use horntail::crypto::{AES_KEY, MapleVersion};
+use horntail::extra::{Diff, DiffOptions, Entry, Schema};
 use horntail::reader::wizet::get_encrypt_version;
-use std::cell::RefCell;
+use horntail::reader::{CodePage, StringDecoder};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
-pub enum Vector {
-    GMS,
-    EMS,
-    NIL,
+pub enum CodePageArg {
+    CP1252,
+    CP949,
+    GBK,
+    BIG5,
+    SJIS,
+    AUTO,
+}
+
+impl From<CodePageArg> for CodePage {
+    fn from(value: CodePageArg) -> Self {
+        match value {
+            CodePageArg::CP1252 => CodePage::Windows1252,
+            CodePageArg::CP949 => CodePage::EucKr,
+            CodePageArg::GBK => CodePage::Gbk,
+            CodePageArg::BIG5 => CodePage::Big5,
+            CodePageArg::SJIS => CodePage::ShiftJis,
+            CodePageArg::AUTO => CodePage::Auto,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+pub enum DiffFormat {
+    Text,
+    Json,
+    Html,
+}
+
+/// cipher of wz files, a region preset or a custom IV and AES key.
+#[derive(Args)]
+struct KeyArgs {
+    /// region preset of wz file vector, gms, ems, kms... or nil
+    #[arg(short, long, value_name = "REGION", value_parser = parse_region, required_unless_present = "iv")]
+    key: Option<Region>,
+    /// custom wz file vector in hex, overrides the one of region
+    #[arg(short, long, value_name = "HEX", value_parser = parse_hex::<4>)]
+    iv: Option<[u8; 4]>,
+    /// custom aes key in hex, overrides the one of region
+    #[arg(short, long, value_name = "HEX", value_parser = parse_hex::<32>)]
+    aes_key: Option<[u8; 32]>,
+}
+
+impl KeyArgs {
+    fn region(&self) -> Region {
+        Region {
+            name: self.key.map(|r| r.name).unwrap_or("custom"),
+            iv: self
+                .iv
+                .or(self.key.map(|r| r.iv))
+                .unwrap_or(MAPLE_VECTOR_NONE),
+            key: self.aes_key.or(self.key.map(|r| r.key)).unwrap_or(AES_KEY),
+        }
+    }
 }
 
 #[derive(Parser)]
@@ -42,9 +92,8 @@ enum Commands {
         /// wz folder or file path
         #[arg(short, long, value_name = "FILE | DIRECTORY", value_hint = clap::ValueHint::AnyPath)]
         path: String,
-        /// wz file vector used
-        #[arg(short, long, value_enum)]
-        key: Vector,
+        #[command(flatten)]
+        key: KeyArgs,
         /// optional version of the wz client used
         #[arg(short, long, value_name = "VERSION")]
         version: u16,
@@ -54,6 +103,61 @@ enum Commands {
         /// disable image preview
         #[arg(short, long, value_name = "DISABLE_PREVIEW", action = clap::ArgAction::SetTrue)]
         disable_preview: Option<bool>,
+        /// code page of single-byte strings
+        #[arg(short, long, value_enum, default_value = "cp1252")]
+        code_page: CodePageArg,
+        /// keep raw bytes of strings which can't be decoded by code page
+        #[arg(short, long, value_name = "LOSSY", action = clap::ArgAction::SetTrue)]
+        lossy: bool,
+    },
+    /// infer schema of images matching a pattern like `Mob/*.img`
+    Schema {
+        /// wz folder or file path
+        #[arg(short, long, value_name = "FILE | DIRECTORY", value_hint = clap::ValueHint::AnyPath)]
+        path: String,
+        #[command(flatten)]
+        key: KeyArgs,
+        /// version of the wz client used
+        #[arg(short, long, value_name = "VERSION")]
+        version: u16,
+        /// only for kmst 777 client
+        #[arg(short, long, value_name = "NO_VERSION", action = clap::ArgAction::SetTrue)]
+        no_version: bool,
+        /// images to walk, relative to the wz root
+        #[arg(short = 'g', long, value_name = "PATTERN")]
+        pattern: String,
+        /// print rust struct skeleton instead of json schema
+        #[arg(short, long, action = clap::ArgAction::SetTrue)]
+        rust: bool,
+    },
+    /// diff two versions of a wz file or folder
+    Diff {
+        /// old wz folder or file path
+        #[arg(short, long, value_name = "FILE | DIRECTORY", value_hint = clap::ValueHint::AnyPath)]
+        path: String,
+        /// new wz folder or file path
+        #[arg(short, long, value_name = "FILE | DIRECTORY", value_hint = clap::ValueHint::AnyPath)]
+        target: String,
+        #[command(flatten)]
+        key: KeyArgs,
+        /// version of the old wz client
+        #[arg(short, long, value_name = "VERSION")]
+        version: u16,
+        /// version of the new wz client, same as old if not given
+        #[arg(long, value_name = "VERSION")]
+        target_version: Option<u16>,
+        /// only for kmst 777 client
+        #[arg(short, long, value_name = "NO_VERSION", action = clap::ArgAction::SetTrue)]
+        no_version: bool,
+        /// canvases with mean pixel difference in 0..=1 not above this are the same
+        #[arg(long, value_name = "THRESHOLD")]
+        threshold: Option<f64>,
+        /// walk images even if their size and checksum are unchanged
+        #[arg(short, long, action = clap::ArgAction::SetTrue)]
+        deep: bool,
+        /// report format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: DiffFormat,
     },
     /// probe wz file version
     Probe {
@@ -63,52 +167,53 @@ enum Commands {
     },
 }
 
+fn parse_region(name: &str) -> Result<Region, String> {
+    if name.eq_ignore_ascii_case("nil") {
+        return Ok(Region::new("nil", MAPLE_VECTOR_NONE, AES_KEY));
+    }
+    horntail::consts::region(name)
+        .copied()
+        .ok_or_else(|| format!("unknown region `{name}`"))
+}
+
+fn parse_hex<const N: usize>(hex: &str) -> Result<[u8; N], String> {
+    let hex = hex.trim_start_matches("0x").replace([' ', ':', '-'], "");
+    if hex.len() != N * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("expect {N} bytes in hex"));
+    }
+    let mut bytes = [0; N];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|e| e.to_string())?;
+    }
+    Ok(bytes)
+}
+
 #[inline]
-fn try_load_packs(structure: Rc<SharedStructure>, path: &Path) -> Option<Vec<HorntailRow>> {
-    let packs = path.join("Packs");
-    if !packs.is_dir() {
+/// `Packs` next to `Data` of the nested structure opened by `Base.wz`.
+fn packs_dir(path: &Path, structure: Structure) -> Option<PathBuf> {
+    if !path.file_stem().map(|x| x == BASE_NAME).unwrap_or(false) {
         return None;
     }
-    PackBundle::with_paths(
-        walkdir::WalkDir::new(packs)
-            .max_depth(1)
-            .follow_links(false)
-            .follow_root_links(false)
-            .into_iter()
-            .filter_map(|x| x.ok())
-            .filter(|x| {
-                let path = x.path();
-                path.is_file()
-                    && path
-                        .extension()
-                        .map(|x| x == PackFile::EXTENSION)
-                        .unwrap_or(false)
-            })
-            .map(|x| x.into_path()),
-        structure.clone(),
-    )?
-    .build_cache()
-    .ok()
+    let Structure::Nested = structure else {
+        return None;
+    };
+    let dir = if path.is_file() {
+        path.parent().and_then(|p| p.parent())?
+    } else {
+        path.parent()?
+    };
+    Some(dir.join("Packs")).filter(|p| p.is_dir())
 }
 
 fn view_command(
     path: String,
-    key: Vector,
+    region: Region,
     version: u16,
     no_version: bool,
     disable_preview: bool,
+    decoder: StringDecoder,
 ) -> Result<(), Error> {
-    let cipher = match key {
-        Vector::GMS => Rc::new(RefCell::new(MapleTable::new(
-            horntail::consts::MAPLE_VECTOR_GLOBAL,
-        )))
-        .into_boxed(),
-        Vector::EMS => Rc::new(RefCell::new(MapleTable::new(
-            horntail::consts::MAPLE_VECTOR_EUROPE,
-        )))
-        .into_boxed(),
-        Vector::NIL => MapleTableNone.into_boxed(),
-    };
+    let cipher = region.cipher();
 
     let root = if let Some(structure) = analyze_wizet_structure(path.as_str()) {
         let shared_structure = Rc::new(SharedStructure {
@@ -116,50 +221,46 @@ fn view_command(
             ver_hash: MapleVersion::from(version).hash(),
             cipher,
             no_version,
+            decoder,
             structure,
         });
 
-        let root = WizetBundle::with_path(path.as_str(), shared_structure.clone())?;
-        let mut leaf = root.build_cache()?;
-
         let p: &Path = path.as_ref();
-        if p.file_stem().map(|x| x == BASE_NAME).unwrap_or(false) {
-            // try load packs file
-            if let Structure::Nested = structure {
-                let dir = if p.is_file() {
-                    p.parent().and_then(|p| p.parent()).unwrap()
-                } else {
-                    p.parent().unwrap()
-                };
-                if let Some(pack_rows) = try_load_packs(shared_structure.clone(), dir) {
-                    pack_rows.into_iter().for_each(|row| {
-                        if let Some(target) = leaf.iter_mut().find(|x| x.name() == row.name()) {
-                            target.leaf = row.leaf;
-                        } else {
-                            leaf.push(row);
-                        }
-                    });
-                }
-            }
-        }
+        if let Some(packs) = packs_dir(p, structure) {
+            let version = MapleVersion::from(version);
+            let data = Entry::from_path(
+                p,
+                shared_structure.cipher.clone_boxed(),
+                version,
+                no_version,
+            )?
+            .with_string_decoder(decoder);
+            let packs = Entry::from_packs(packs, version)?.with_string_decoder(decoder);
+            let overlay = Entry::overlay("Root", vec![data, packs]);
+            HorntailRow::with_entry(overlay, p.to_path_buf(), shared_structure)
+        } else {
+            let root = WizetBundle::with_path(path.as_str(), shared_structure.clone())?;
+            let mut leaf = root.build_cache()?;
 
-        let size = leaf.len();
-        HorntailRow::sort_rows(&mut leaf);
-        HorntailRow {
-            name: string_pool_get("Root".to_string()),
-            offset: 0,
-            group: Rc::new(IndexGroup {
-                parent_offset: 0,
-                file: Rc::new(root.parent.clone()),
-                structure: root.structure.clone(),
-                builder: None,
-            }),
-            desc: string_empty(),
-            leaf: Some(leaf),
-            flag_and_size: ROW_FLAG_EXPANDED
-                | ROW_FLAG_INITIALIZED
-                | size as u64
-                | u64::from(IndexKind::Element(EntryKind::Folder)),
+            let size = leaf.len();
+            HorntailRow::sort_rows(&mut leaf);
+            HorntailRow {
+                name: string_pool_get("Root".to_string()),
+                offset: 0,
+                group: Rc::new(IndexGroup {
+                    parent_offset: 0,
+                    file: Rc::new(root.parent.clone()),
+                    structure: root.structure.clone(),
+                    builder: None,
+                    entry: None,
+                }),
+                desc: string_empty(),
+                leaf: Some(leaf),
+                flag_and_size: ROW_FLAG_EXPANDED
+                    | ROW_FLAG_INITIALIZED
+                    | size as u64
+                    | u64::from(IndexKind::Element(EntryKind::Folder)),
+            }
         }
     } else {
         let shared_structure = Rc::new(SharedStructure {
@@ -167,50 +268,16 @@ fn view_command(
             ver_hash: MapleVersion::from(version).hash(),
             cipher,
             no_version,
+            decoder,
             structure: Structure::Flattened,
         });
 
         let p: &Path = path.as_ref();
-        let bundle = if p.is_file() {
-            PackBundle::with_paths(Some(p), shared_structure.clone())
-        } else {
-            PackBundle::with_paths(
-                walkdir::WalkDir::new(path)
-                    .max_depth(1)
-                    .follow_links(false)
-                    .follow_root_links(false)
-                    .into_iter()
-                    .filter_map(|x| x.ok())
-                    .filter(|x| x.path().extension().map(|x| x == "ms").unwrap_or(false))
-                    .map(|x| x.into_path()),
-                shared_structure.clone(),
-            )
+        let packs = match Entry::from_packs(p, MapleVersion::from(version)) {
+            Err(horntail::Error::InvalidArgument) => return Err(Error::InvalidPackPaths),
+            packs => packs?.with_string_decoder(decoder),
         };
-
-        let Some(bundle) = bundle else {
-            return Err(Error::InvalidPackPaths);
-        };
-
-        let mut rows = bundle.build_cache()?;
-        let size = rows.len();
-
-        HorntailRow::sort_rows(&mut rows);
-        HorntailRow {
-            name: string_pool_get("Root".to_string()),
-            offset: 0,
-            desc: string_empty(),
-            group: Rc::new(IndexGroup {
-                parent_offset: 0,
-                file: Rc::new(PathBuf::default()),
-                structure: shared_structure.clone(),
-                builder: None,
-            }),
-            leaf: Some(rows),
-            flag_and_size: ROW_FLAG_EXPANDED
-                | ROW_FLAG_INITIALIZED
-                | size as u64
-                | u64::from(IndexKind::Element(EntryKind::Folder)),
-        }
+        HorntailRow::with_entry(packs, p.to_path_buf(), shared_structure)
     };
 
     let result = app::serve(root, disable_preview);
@@ -218,6 +285,63 @@ fn view_command(
     result
 }
 
+fn schema_command(
+    path: String,
+    region: Region,
+    version: u16,
+    no_version: bool,
+    pattern: String,
+    rust: bool,
+) -> Result<(), Error> {
+    let root = Entry::from_path(
+        &path,
+        region.cipher(),
+        MapleVersion::from(version),
+        no_version,
+    )?;
+    let schema = Schema::try_infer(&root, &pattern)?;
+    if rust {
+        let name = Path::new(root.name())
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(root.name());
+        print!("{}", schema.to_rust(name));
+    } else {
+        println!("{}", schema.to_json_schema()?);
+    }
+    Ok(())
+}
+
+fn diff_command(
+    path: String,
+    target: String,
+    region: Region,
+    (version, target_version): (u16, u16),
+    no_version: bool,
+    opts: DiffOptions,
+    format: DiffFormat,
+) -> Result<(), Error> {
+    let old = Entry::from_path(
+        &path,
+        region.cipher(),
+        MapleVersion::from(version),
+        no_version,
+    )?;
+    let new = Entry::from_path(
+        &target,
+        region.cipher(),
+        MapleVersion::from(target_version),
+        no_version,
+    )?;
+    let diff = Diff::try_diff(&old, &new, opts)?;
+    match format {
+        DiffFormat::Text => print!("{}", diff.to_text()),
+        DiffFormat::Json => println!("{}", diff.to_json()?),
+        DiffFormat::Html => print!("{}", diff.to_html()),
+    }
+    Ok(())
+}
+
 fn probe_command(path: String) -> Result<(), Error> {
     let enc_ver = get_encrypt_version(path)?;
     let possible = (0..u16::MAX)
@@ -237,13 +361,90 @@ fn main() -> Result<(), Error> {
             version,
             no_version,
             disable_preview,
+            code_page,
+            lossy,
         } => view_command(
             path,
-            key,
+            key.region(),
             version,
             no_version,
             disable_preview.unwrap_or_default(),
+            StringDecoder::new(code_page.into(), lossy),
+        ),
+        Commands::Schema {
+            path,
+            key,
+            version,
+            no_version,
+            pattern,
+            rust,
+        } => schema_command(path, key.region(), version, no_version, pattern, rust),
+        Commands::Diff {
+            path,
+            target,
+            key,
+            version,
+            target_version,
+            no_version,
+            threshold,
+            deep,
+            format,
+        } => diff_command(
+            path,
+            target,
+            key.region(),
+            (version, target_version.unwrap_or(version)),
+            no_version,
+            DiffOptions {
+                canvas_threshold: threshold,
+                deep,
+            },
+            format,
         ),
         Commands::Probe { path } => probe_command(path),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_digits_only() {
+        assert_eq!(parse_hex::<4>("0x4d23c72b"), Ok([0x4d, 0x23, 0xc7, 0x2b]));
+        assert_eq!(parse_hex::<4>("4D:23:C7:2B"), Ok([0x4d, 0x23, 0xc7, 0x2b]));
+        // `from_str_radix` takes a sign
+        assert!(parse_hex::<2>("+f+f").is_err());
+        assert!(parse_hex::<2>("0g00").is_err());
+        assert!(parse_hex::<2>("000").is_err());
+    }
+
+    #[test]
+    fn key_options_of_every_command() {
+        let commands: [&[&str]; 3] = [
+            &["view", "-p", "Base.wz"],
+            &["schema", "-p", "Mob.wz", "-g", "*.img"],
+            &["diff", "-p", "old", "-t", "new"],
+        ];
+        for command in commands {
+            let args = ["horntail"]
+                .iter()
+                .chain(command)
+                .chain(&["-v", "95", "--iv", "4d23c72b"]);
+            let key = match Cli::try_parse_from(args).unwrap().command {
+                Commands::View { key, .. } => key,
+                Commands::Schema { key, .. } => key,
+                Commands::Diff { key, .. } => key,
+                Commands::Probe { .. } => unreachable!(),
+            };
+            let region = key.region();
+            assert_eq!(region.name, "custom");
+            assert_eq!(region.iv, [0x4d, 0x23, 0xc7, 0x2b]);
+            assert_eq!(region.key, AES_KEY);
+        }
+        let args = [
+            "horntail", "schema", "-p", "Mob.wz", "-g", "*.img", "-v", "95",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::bundle::analyze::{BASE_NAME, Structure};
 use crate::bundle::{SharedStructure, WizetBundle};
 use crate::optimize::{RefString, string_empty, string_pool_get};
+use horntail::extra::{Entry, EntryValue};
 use horntail::reader::wizet::WizetFile;
 use horntail::reader::{Accessor, seek_back};
 use horntail::{
@@ -53,6 +54,7 @@ impl IndexKind {
         match self {
             IndexKind::Primitive(p) => p.as_str(),
             IndexKind::Element(e) => match e {
+                EntryKind::Unknown => "Unknown",
                 EntryKind::Folder => "Folder",
                 EntryKind::Image(i) => match i {
                     ImageKind::Canvas => "Canvas",
@@ -95,6 +97,7 @@ impl From<u64> for IndexKind {
             16 => IndexKind::Element(EntryKind::Image(ImageKind::Script)),
             17 => IndexKind::Element(EntryKind::Property(PropertyKind::Encode)),
             18 => IndexKind::Element(EntryKind::Property(PropertyKind::Plain)),
+            19 => IndexKind::Element(EntryKind::Unknown),
             _ => unreachable!(),
         }
     }
@@ -121,6 +124,7 @@ impl From<IndexKind> for u64 {
             IndexKind::Element(EntryKind::Image(ImageKind::Script)) => 16,
             IndexKind::Element(EntryKind::Property(PropertyKind::Encode)) => 17,
             IndexKind::Element(EntryKind::Property(PropertyKind::Plain)) => 18,
+            IndexKind::Element(EntryKind::Unknown) => 19,
         };
         flag << 56
     }
@@ -131,6 +135,8 @@ pub struct IndexGroup {
     pub file: Rc<PathBuf>,
     pub structure: Rc<SharedStructure>,
     pub builder: Option<Box<dyn AccessorBuilder>>,
+    /// `extra::Entry` of the row, folders list their children from it.
+    pub entry: Option<Rc<Entry>>,
 }
 
 pub struct HorntailRow {
@@ -181,6 +187,27 @@ impl HorntailRow {
         self.flag_and_size & ROW_FLAG_EXPANDED == ROW_FLAG_EXPANDED
     }
 
+    /// expanded root row listing the children of `entry`.
+    pub fn with_entry(entry: Entry, file: PathBuf, structure: Rc<SharedStructure>) -> HorntailRow {
+        let mut root = HorntailRow {
+            name: string_pool_get("Root".to_string()),
+            offset: 0,
+            desc: string_empty(),
+            group: Rc::new(IndexGroup {
+                parent_offset: 0,
+                file: Rc::new(file),
+                structure,
+                builder: None,
+                entry: Some(Rc::new(entry)),
+            }),
+            leaf: None,
+            flag_and_size: ROW_FLAG_EXPANDED | u64::from(IndexKind::Element(EntryKind::Folder)),
+        };
+        root.initialize();
+        root.flag_and_size |= root.children().len() as u64;
+        root
+    }
+
     pub fn sort_rows(rows: &mut [HorntailRow]) {
         let is_all_word = rows
             .iter()
@@ -227,16 +254,31 @@ impl HorntailRow {
         }
 
         self.flag_and_size |= ROW_FLAG_INITIALIZED;
-        let rows = build_rows(
-            &self.name,
-            AccessorOpt {
-                offset: self.offset,
-                ver_hash: self.group.structure.ver_hash,
-                parent_offset: self.group.parent_offset,
-            },
-            kind,
-            &self.group,
-        );
+        let rows = match self.group.entry.as_ref() {
+            Some(entry) if matches!(kind, IndexKind::Element(EntryKind::Folder)) => {
+                build_entry_rows(entry, &self.group)
+            }
+            // pack images are decrypted here, errors are shown as the value
+            entry => entry
+                .map(|e| e.try_builder().map(|_| ()))
+                .transpose()
+                .map(|_| {
+                    build_rows(
+                        &self.name,
+                        AccessorOpt {
+                            offset: self.offset,
+                            ver_hash: self.group.structure.ver_hash,
+                            parent_offset: self.group.parent_offset,
+                        },
+                        kind,
+                        &self.group,
+                    )
+                }),
+        };
+        let rows = rows.unwrap_or_else(|e| {
+            self.desc = string_pool_get(format!("error: {e}"));
+            None
+        });
 
         if let Some(rows) = rows {
             if let Some(sl) = self.leaf.as_mut() {
@@ -254,12 +296,12 @@ impl HorntailRow {
     #[inline]
     fn adjust_expand_size(&mut self) {
         self.flag_and_size &= 0xFF00000000000000;
-        if self.is_expand() {
-            if let Some(leaf) = self.leaf.as_ref() {
-                self.flag_and_size |= leaf
-                    .iter()
-                    .fold(0, |acc, leaf| acc + leaf.expand_size() + 1);
-            }
+        if self.is_expand()
+            && let Some(leaf) = self.leaf.as_ref()
+        {
+            self.flag_and_size |= leaf
+                .iter()
+                .fold(0, |acc, leaf| acc + leaf.expand_size() + 1);
         }
     }
 
@@ -471,8 +513,8 @@ fn get_image_value(opt: AccessorOpt, kind: ImageKind, accessor: &mut dyn Accesso
         ImageKind::Canvas => {
             let attr = CanvasAttribute::from_accessor(opt, accessor);
             string_pool_get(format!(
-                "{} (W:{}, H:{})",
-                attr.format, attr.size.x, attr.size.y
+                "{} (W:{}, H:{}, Scale:{}, Flags:{:#x})",
+                attr.format, attr.size.x, attr.size.y, attr.scale, attr.flags
             ))
         }
         ImageKind::Video => {
@@ -515,7 +557,7 @@ fn get_element_kind_value(
     accessor: &mut dyn Accessor,
 ) -> RefString {
     match ik {
-        EntryKind::Folder | EntryKind::Property(_) => string_empty(),
+        EntryKind::Unknown | EntryKind::Folder | EntryKind::Property(_) => string_empty(),
         EntryKind::Image(img) => get_image_value(opt, img, accessor),
     }
 }
@@ -593,6 +635,10 @@ fn process_folder(
         .into_inner()
         .into_iter()
         .map(|f| HorntailRow {
+            desc: f
+                .raw
+                .map(|raw| string_pool_get(format!("{raw:02x?}")))
+                .unwrap_or_else(string_empty),
             name: string_pool_get(f.name),
             offset: f.offset,
             group: Rc::new(IndexGroup {
@@ -600,8 +646,8 @@ fn process_folder(
                 file: group.file.clone(),
                 structure: group.structure.clone(),
                 builder: group.builder.as_ref().map(|x| x.clone_boxed()),
+                entry: None,
             }),
-            desc: string_empty(),
             leaf: None,
             flag_and_size: u64::from(IndexKind::Element(f.kind)),
         })
@@ -621,10 +667,10 @@ fn process_folder(
     if let Structure::Flattened = group.structure.structure {
         try_path = try_path.with_extension(WizetFile::EXTENSION);
     }
-    if try_path != group.file.as_path() {
-        if let Ok(bundle) = WizetBundle::with_path(try_path, group.structure.clone()) {
-            rows.extend(bundle.build_cache().unwrap());
-        }
+    if try_path != group.file.as_path()
+        && let Ok(bundle) = WizetBundle::with_path(try_path, group.structure.clone())
+    {
+        rows.extend(bundle.build_cache().unwrap());
     }
 
     if rows.is_empty() { None } else { Some(rows) }
@@ -670,7 +716,8 @@ pub fn has_leaf(kind: IndexKind) -> bool {
         kind,
         IndexKind::Element(EntryKind::Image(
             ImageKind::Vector2D | ImageKind::UOL | ImageKind::Convex2D,
-        )) | IndexKind::Primitive(_)
+        )) | IndexKind::Element(EntryKind::Unknown)
+            | IndexKind::Primitive(_)
     )
 }
 
@@ -685,6 +732,7 @@ pub fn build_rows(
     };
     let mut accessor = opt.accessor(group.builder.as_ref().map(|x| x.as_ref()).unwrap());
     match e {
+        EntryKind::Unknown => {}
         EntryKind::Folder => {
             return process_folder(name, opt, group, accessor.as_mut());
         }
@@ -716,3 +764,73 @@ pub fn build_rows(
     };
     None
 }
+
+/// accessor of an `extra::Entry` image, pack images are decrypted on first access.
+struct EntryAccessorBuilder(Rc<Entry>);
+
+impl AccessorBuilder for EntryAccessorBuilder {
+    fn clone_boxed(&self) -> Box<dyn AccessorBuilder> {
+        Box::new(EntryAccessorBuilder(self.0.clone()))
+    }
+
+    /// rows check `Entry::try_builder` before reading, it is cached then.
+    fn accessor(&self) -> Box<dyn Accessor> {
+        self.0
+            .try_builder()
+            .unwrap_or_else(|e| panic!("entry `{}`: {e}", self.0.name()))
+            .accessor()
+    }
+}
+
+/// children of a folder entry, sub folders are listed from entry and images
+/// are read like the ones of `wz` files.
+fn build_entry_rows(
+    entry: &Entry,
+    group: &Rc<IndexGroup>,
+) -> Result<Option<Vec<HorntailRow>>, horntail::Error> {
+    let rows = entry
+        .try_iter()?
+        .map(|child| {
+            // children of folders are always kinds
+            let kind = match child.value() {
+                EntryValue::Kind(kind) => *kind,
+                EntryValue::Primitive(_) => EntryKind::Unknown,
+            };
+            let name = string_pool_get(child.name().to_owned());
+            let opt = child.accessor_opt();
+            let child = Rc::new(child);
+            let (offset, group) = if let EntryKind::Folder = kind {
+                (
+                    0,
+                    IndexGroup {
+                        parent_offset: 0,
+                        file: group.file.clone(),
+                        structure: group.structure.clone(),
+                        builder: None,
+                        entry: Some(child),
+                    },
+                )
+            } else {
+                (
+                    opt.offset,
+                    IndexGroup {
+                        parent_offset: opt.parent_offset,
+                        file: group.file.clone(),
+                        structure: group.structure.clone(),
+                        builder: Some(EntryAccessorBuilder(child.clone()).into_boxed()),
+                        entry: Some(child),
+                    },
+                )
+            };
+            HorntailRow {
+                name,
+                offset,
+                group: Rc::new(group),
+                desc: string_empty(),
+                leaf: None,
+                flag_and_size: u64::from(IndexKind::Element(kind)),
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(if rows.is_empty() { None } else { Some(rows) })
+}
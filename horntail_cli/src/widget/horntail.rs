@@ -228,11 +228,12 @@ impl<'a> StatefulWidget for HorntailView<'a> {
     }
 }
 
-fn colorful_data_type(row: &HorntailRow) -> Span {
+fn colorful_data_type(row: &HorntailRow) -> Span<'_> {
     let style = match IndexKind::from(row.flag_and_size) {
         IndexKind::Primitive(_) => Style::new().light_green(),
         IndexKind::Element(kind) => match kind {
             EntryKind::Folder | EntryKind::Property(_) => Style::new().light_cyan(),
+            EntryKind::Unknown => Style::new().light_red(),
             EntryKind::Image(ik) => match ik {
                 ImageKind::Canvas | ImageKind::Video | ImageKind::Sound => {
                     Style::new().light_yellow()
@@ -285,8 +286,8 @@ fn get_index_by_paths(root: &HorntailRow, paths: &[u64]) -> u64 {
         - 1
 }
 
-fn build_table_rows(root: &HorntailRow, view_offset: u64, mut view_size: usize) -> Vec<Row> {
-    fn _build_row(prefix: String, row: &HorntailRow) -> Row {
+fn build_table_rows(root: &HorntailRow, view_offset: u64, mut view_size: usize) -> Vec<Row<'_>> {
+    fn _build_row(prefix: String, row: &HorntailRow) -> Row<'_> {
         Row::new([
             Line::from(vec![
                 Span::styled(prefix, Style::new().light_blue()),